@@ -0,0 +1,65 @@
+// Witness assignment and MockProver verification of the rmd160 chip
+// hashing 1, 4 and 16 blocks, run with `cargo bench`. The witness
+// precomputation of 16 blocks is compared on one thread and on all
+// threads with `cargo bench --features parallel`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::halo2curves::bn256::Fr;
+
+use mylib::circuits::bench::{build_circuit, dummy_blocks};
+use mylib::circuits::rmd160::{precompute_all_witnesses, RMD160Chip};
+use mylib::host::rmd160::H0;
+
+/// rows MockProver reserves for blinding at the end of the columns
+const BLINDING_ROWS: usize = 10;
+
+/// the smallest k holding the rows of the blocks and the lookup tables
+fn circuit_k(num_blocks: usize) -> u32 {
+    let rows = RMD160Chip::<Fr>::estimated_rows(num_blocks) + BLINDING_ROWS;
+    ((rows as f64).log2().ceil() as u32).max(RMD160Chip::<Fr>::layout_info().min_k)
+}
+
+fn bench_rmd160(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rmd160");
+    group.sample_size(10);
+    for num_blocks in [1, 4, 16] {
+        let k = circuit_k(num_blocks);
+        let (circuit, instances) = build_circuit::<Fr>(&dummy_blocks(num_blocks));
+        println!("rmd160 {} blocks: {} rows, k = {}",
+            num_blocks, RMD160Chip::<Fr>::estimated_rows(num_blocks), k);
+
+        group.bench_with_input(BenchmarkId::new("assign", num_blocks), &num_blocks, |b, _| {
+            b.iter(|| MockProver::run(k, &circuit, vec![instances.clone()]).unwrap())
+        });
+
+        let prover = MockProver::run(k, &circuit, vec![instances.clone()]).unwrap();
+        group.bench_with_input(BenchmarkId::new("verify", num_blocks), &num_blocks, |b, _| {
+            b.iter(|| assert_eq!(prover.verify(), Ok(())))
+        });
+    }
+    group.finish();
+}
+
+fn bench_witnesses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rmd160 witnesses");
+    let blocks = dummy_blocks(16);
+    #[cfg(feature = "parallel")]
+    {
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        group.bench_function("16 blocks, 1 thread", |b| {
+            b.iter(|| single.install(|| precompute_all_witnesses::<Fr>(&H0, &blocks)))
+        });
+        group.bench_function(format!("16 blocks, {} threads", rayon::current_num_threads()), |b| {
+            b.iter(|| precompute_all_witnesses::<Fr>(&H0, &blocks))
+        });
+    }
+    #[cfg(not(feature = "parallel"))]
+    group.bench_function("16 blocks", |b| {
+        b.iter(|| precompute_all_witnesses::<Fr>(&H0, &blocks))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_rmd160, bench_witnesses);
+criterion_main!(benches);
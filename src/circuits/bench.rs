@@ -0,0 +1,176 @@
+// Inputs and a circuit to set up prover benchmarks of the rmd160 chip.
+
+use halo2_proofs::{
+    circuit::{Layouter, AssignedCell, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Instance,
+    },
+};
+
+use crate::circuits::rmd160::{
+    AssignedDigest,
+    RMD160Chip,
+    RMD160Config,
+    RMD160Field,
+};
+use crate::host::rmd160::{H0, message_blocks, rmd160_block_words};
+
+/// a representative block: every word has bits set in all four bytes
+/// so the carries and the limbs of the rounds are not trivially zero
+pub fn dummy_inputs() -> [u32; 16] {
+    let mut words = [0u32; 16];
+    for (i, w) in words.iter_mut().enumerate() {
+        *w = 0x9e3779b9u32.wrapping_mul(i as u32 + 1);
+    }
+    words
+}
+
+/// the padded blocks of a dummy message just long enough to need
+/// num_blocks blocks
+pub fn dummy_blocks(num_blocks: usize) -> Vec<[u32; 16]> {
+    assert!(num_blocks > 0, "a padded message has at least one block");
+    let message = (0..num_blocks * 64 - 9).map(|i| i as u8).collect::<Vec<_>>();
+    let blocks = message_blocks(&message);
+    debug_assert_eq!(blocks.len(), num_blocks);
+    blocks
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+    rmd160config: RMD160Config,
+    words: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+/// Hash the blocks from H0 and expose the digest words at the instance
+/// rows 0 .. 5. The blocks are taken as they are, so padded blocks give
+/// the digest of the message and raw blocks the chained compress output.
+#[derive(Clone, Debug, Default)]
+pub struct BenchCircuit {
+    pub blocks: Vec<[u32; 16]>,
+}
+
+/// the circuit hashing the blocks together with its instance column
+pub fn build_circuit<F: RMD160Field>(blocks: &[[u32; 16]]) -> (BenchCircuit, Vec<F>) {
+    let digest = blocks.iter().fold(H0, |state, block| rmd160_block_words(state, *block));
+    let instances = digest.iter().map(|w| F::from(*w as u64)).collect();
+    (BenchCircuit { blocks: blocks.to_vec() }, instances)
+}
+
+impl BenchCircuit {
+    fn assign_block<F: RMD160Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+        block: &[u32; 16],
+    ) -> Result<[AssignedCell<F, F>; 16], Error> {
+        layouter.assign_region(
+            || "bench block",
+            |mut region| {
+                let mut cells = vec![];
+                for (i, w) in block.iter().enumerate() {
+                    cells.push(region.assign_advice(
+                        || format!("block word {}", i),
+                        column,
+                        i,
+                        || Value::known(F::from(*w as u64))
+                    )?);
+                }
+                Ok(cells.try_into().unwrap())
+            }
+        )
+    }
+
+    fn assign_iv<F: RMD160Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+    ) -> Result<[AssignedCell<F, F>; 5], Error> {
+        layouter.assign_region(
+            || "bench iv",
+            |mut region| {
+                let mut cells = vec![];
+                for (i, w) in H0.iter().enumerate() {
+                    cells.push(region.assign_advice_from_constant(
+                        || format!("iv {}", i),
+                        column,
+                        i,
+                        F::from(*w as u64)
+                    )?);
+                }
+                Ok(cells.try_into().unwrap())
+            }
+        )
+    }
+}
+
+impl<F: RMD160Field> Circuit<F> for BenchCircuit {
+    type Config = BenchConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        BenchCircuit { blocks: vec![[0; 16]; self.blocks.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let words = meta.advice_column();
+        meta.enable_equality(words);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        BenchConfig {
+            rmd160config: RMD160Chip::<F>::configure(meta),
+            words,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let rmd160chip = RMD160Chip::<F>::new(config.rmd160config.clone());
+        rmd160chip.load_range_table(&mut layouter)?;
+        let iv = self.assign_iv(&mut layouter, config.words)?;
+        let mut blocks = vec![];
+        for block in self.blocks.iter() {
+            blocks.push(self.assign_block(&mut layouter, config.words, block)?);
+        }
+        let digest = rmd160chip.assign_prepadded(&mut layouter, &iv, &blocks)?;
+        AssignedDigest::from_cells(digest).verify_against_instance(&mut layouter, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::arithmetic::Field;
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use halo2_proofs::dev::MockProver;
+
+    use super::{build_circuit, dummy_blocks, dummy_inputs};
+    use crate::host::rmd160::{hash_prepadded, rmd160_digest};
+
+    const K: u32 = 16;
+
+    #[test]
+    fn test_dummy_inputs() {
+        let (circuit, instances) = build_circuit::<Fr>(&[dummy_inputs()]);
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_dummy_blocks() {
+        let blocks = dummy_blocks(2);
+        let message = (0..2 * 64 - 9).map(|i| i as u8).collect::<Vec<_>>();
+        assert_eq!(hash_prepadded(&blocks), rmd160_digest(&message));
+        let (circuit, instances) = build_circuit::<Fr>(&blocks);
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let (circuit, mut instances) = build_circuit::<Fr>(&blocks);
+        instances[4] += Fr::one();
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
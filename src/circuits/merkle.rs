@@ -0,0 +1,214 @@
+// Membership proof in a RIPEMD-160 Merkle tree.
+
+use halo2_proofs::{
+    circuit::{Layouter, AssignedCell, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Instance,
+    },
+};
+
+use crate::circuits::rmd160::{
+    AssignedDigest,
+    RMD160Chip,
+    RMD160Config,
+    RMD160Field,
+};
+use crate::utils::digest_bytes_to_words;
+
+#[derive(Clone, Debug)]
+pub struct MerkleConfig {
+    rmd160config: RMD160Config,
+    path: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+/// Prove that leaf is at the position given by index_bits in the tree of
+/// depth DEPTH. The root is exposed at the instance rows 0 .. 5 and the
+/// leaf at the rows 5 .. 10, both as little endian digest words.
+/// index_bits[i] is set when the node at level i is the right child.
+///
+/// Leaves and parents are hashed under the constant tags MERKLE_LEAF_TAG
+/// and MERKLE_NODE_TAG, so an internal node can not be proven as a leaf
+/// (see RMD160Chip::merkle_path_root).
+#[derive(Clone, Debug)]
+pub struct MerkleCircuit<const DEPTH: usize> {
+    pub leaf: [u8; 20],
+    pub path: [[u8; 20]; DEPTH],
+    pub index_bits: [bool; DEPTH],
+}
+
+impl<const DEPTH: usize> Default for MerkleCircuit<DEPTH> {
+    fn default() -> Self {
+        MerkleCircuit {
+            leaf: [0; 20],
+            path: [[0; 20]; DEPTH],
+            index_bits: [false; DEPTH],
+        }
+    }
+}
+
+impl<const DEPTH: usize> MerkleCircuit<DEPTH> {
+    /// the instance column expected by the circuit: root words then leaf words
+    pub fn instances<F: RMD160Field>(root: &[u8; 20], leaf: &[u8; 20]) -> Vec<F> {
+        digest_bytes_to_words(*root).iter().chain(digest_bytes_to_words(*leaf).iter())
+            .map(|w| F::from(*w as u64))
+            .collect()
+    }
+
+    fn assign_digest<F: RMD160Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+        digest: &[u8; 20],
+    ) -> Result<AssignedDigest<F>, Error> {
+        layouter.assign_region(
+            || "path digest",
+            |mut region| {
+                let mut cells = vec![];
+                for (i, w) in digest_bytes_to_words(*digest).iter().enumerate() {
+                    cells.push(region.assign_advice(
+                        || format!("digest word {}", i),
+                        column,
+                        i,
+                        || Value::known(F::from(*w as u64))
+                    )?);
+                }
+                Ok(AssignedDigest::from_cells(cells.try_into().unwrap()))
+            }
+        )
+    }
+
+    fn assign_bit<F: RMD160Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        column: Column<Advice>,
+        bit: bool,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "index bit",
+            |mut region| {
+                region.assign_advice(
+                    || "index bit",
+                    column,
+                    0,
+                    || Value::known(if bit { F::one() } else { F::zero() })
+                )
+            }
+        )
+    }
+}
+
+impl<F: RMD160Field, const DEPTH: usize> Circuit<F> for MerkleCircuit<DEPTH> {
+    type Config = MerkleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let path = meta.advice_column();
+        meta.enable_equality(path);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        MerkleConfig {
+            rmd160config: RMD160Chip::<F>::configure(meta),
+            path,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let rmd160chip = RMD160Chip::<F>::new(config.rmd160config.clone());
+        rmd160chip.load_range_table(&mut layouter)?;
+        let leaf = self.assign_digest(&mut layouter, config.path, &self.leaf)?;
+        leaf.verify_against_instance(&mut layouter, config.instance, 5)?;
+        let mut path = vec![];
+        for level in 0..DEPTH {
+            let sibling = self.assign_digest(&mut layouter, config.path, &self.path[level])?;
+            let bit = self.assign_bit(&mut layouter, config.path, self.index_bits[level])?;
+            path.push((bit, sibling));
+        }
+        let root = rmd160chip.merkle_path_root(&mut layouter, &leaf, &path)?;
+        root.verify_against_instance(&mut layouter, config.instance, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use halo2_proofs::dev::MockProver;
+
+    use super::MerkleCircuit;
+    use crate::host::rmd160::{merkle_leaf, merkle_node, merkle_root, rmd160_digest};
+
+    const K: u32 = 16;
+
+    /// the leaves of a tree with four leaves and the two levels above them
+    fn tree() -> ([[u8; 20]; 4], [[u8; 20]; 2], [u8; 20]) {
+        let leaves = [0u8, 1, 2, 3].map(|i| rmd160_digest(&[i; 7]));
+        let hashed = leaves.map(|leaf| merkle_leaf(&leaf));
+        let nodes = [0, 1].map(|i| merkle_node(&hashed[2*i], &hashed[2*i+1]));
+        let root = merkle_node(&nodes[0], &nodes[1]);
+        (leaves, nodes, root)
+    }
+
+    #[test]
+    fn test_merkle_membership() {
+        let (leaves, nodes, root) = tree();
+        for index in 0..4 {
+            let circuit = MerkleCircuit::<2> {
+                leaf: leaves[index],
+                path: [merkle_leaf(&leaves[index ^ 1]), nodes[1 - index / 2]],
+                index_bits: [index & 1 == 1, index & 2 == 2],
+            };
+            assert_eq!(merkle_root(&circuit.leaf, &circuit.path, &circuit.index_bits), root);
+            let instances = MerkleCircuit::<2>::instances::<Fr>(&root, &leaves[index]);
+            let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "leaf {}", index);
+        }
+    }
+
+    #[test]
+    fn test_merkle_wrong_path() {
+        let (leaves, nodes, root) = tree();
+        let instances = MerkleCircuit::<2>::instances::<Fr>(&root, &leaves[0]);
+
+        // a wrong sibling
+        let circuit = MerkleCircuit::<2> {
+            leaf: leaves[0],
+            path: [merkle_leaf(&leaves[2]), nodes[1]],
+            index_bits: [false, false],
+        };
+        let prover = MockProver::run(K, &circuit, vec![instances.clone()]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the right siblings in the wrong order
+        let circuit = MerkleCircuit::<2> {
+            leaf: leaves[0],
+            path: [merkle_leaf(&leaves[1]), nodes[1]],
+            index_bits: [true, false],
+        };
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_node_as_leaf() {
+        let (_, nodes, root) = tree();
+        // nodes[0] is the left child of the root but hashes as a leaf first
+        assert_ne!(merkle_root(&nodes[0], &[nodes[1]], &[false]), root);
+        let circuit = MerkleCircuit::<1> {
+            leaf: nodes[0],
+            path: [nodes[1]],
+            index_bits: [false],
+        };
+        let instances = MerkleCircuit::<1>::instances::<Fr>(&root, &nodes[0]);
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
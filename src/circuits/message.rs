@@ -0,0 +1,147 @@
+// A complete circuit proving the RIPEMD-160 digest of a message.
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use crate::circuits::rmd160::{
+    RMD160Chip,
+    RMD160Config,
+    RMD160ConfigBuilder,
+    RMD160Field,
+};
+use crate::host::rmd160::rmd160_digest;
+use crate::utils::digest_bytes_to_words;
+
+/// Prove knowledge of a message of a fixed length whose digest is public.
+/// The message bytes are private witnesses, range checked and padded in
+/// circuit, and the digest words are exposed at the instance rows 0 .. 5
+/// (see RMD160Chip::constrain_digest_public).
+///
+/// The length is part of the circuit: hash_bytes binds it to a constant, so
+/// the keys generated for a message of n bytes only prove messages of n
+/// bytes, even shorter ones of the same block count are rejected.
+/// without_witnesses keeps the length.
+#[derive(Clone, Debug, Default)]
+pub struct Rmd160Circuit {
+    pub message: Vec<u8>,
+}
+
+impl Rmd160Circuit {
+    pub fn new(message: &[u8]) -> Self {
+        Rmd160Circuit { message: message.to_vec() }
+    }
+
+    /// the instance column expected for the digest
+    pub fn instances<F: RMD160Field>(digest: &[u8; 20]) -> Vec<F> {
+        digest_bytes_to_words(*digest).iter().map(|w| F::from(*w as u64)).collect()
+    }
+
+    /// the instance column of the digest of the message of the circuit
+    pub fn expected_instances<F: RMD160Field>(&self) -> Vec<F> {
+        Self::instances(&rmd160_digest(&self.message))
+    }
+}
+
+impl<F: RMD160Field> Circuit<F> for Rmd160Circuit {
+    type Config = RMD160Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Rmd160Circuit { message: vec![0; self.message.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RMD160ConfigBuilder::new().enable_public_digest(true).configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let rmd160chip = RMD160Chip::<F>::new(config);
+        rmd160chip.load_range_table(&mut layouter)?;
+        let digest = rmd160chip.hash_bytes(&mut layouter, &self.message)?;
+        rmd160chip.constrain_digest_public(&mut layouter, digest.cells())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
+    use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+    use halo2_proofs::poly::kzg::multiopen::{ProverGWC, VerifierGWC};
+    use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+    use halo2_proofs::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
+    use rand::SeedableRng;
+
+    use super::Rmd160Circuit;
+    use crate::host::rmd160::rmd160_digest;
+
+    const K: u32 = 16;
+
+    #[test]
+    fn test_rmd160_circuit() {
+        let circuit = Rmd160Circuit::new(b"message digest");
+        let instances = circuit.expected_instances::<Fr>();
+        let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let wrong = Rmd160Circuit::instances::<Fr>(&rmd160_digest(b"message digesT"));
+        let prover = MockProver::run(K, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// keygen for keys, then create_proof for circuit and verify_proof with
+    /// the KZG backend on bn256
+    fn prove_and_verify(keys: &Rmd160Circuit, circuit: &Rmd160Circuit, instances: &[Fr]) -> bool {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+        let empty = <Rmd160Circuit as Circuit<Fr>>::without_witnesses(keys);
+        let vk = keygen_vk(&params, &empty).unwrap();
+        let pk = keygen_pk(&params, vk, &empty).unwrap();
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<'_, Bn256>, _, _, _, _>(
+            &params,
+            &pk,
+            &[circuit.clone()],
+            &[&[instances]],
+            &mut rng,
+            &mut transcript,
+        ).unwrap();
+        let proof = transcript.finalize();
+
+        let strategy = SingleStrategy::new(&params);
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierGWC<'_, Bn256>, _, _, _>(
+            params.verifier_params(),
+            pk.get_vk(),
+            strategy,
+            &[&[instances]],
+            &mut transcript,
+        ).is_ok()
+    }
+
+    #[test]
+    fn test_rmd160_circuit_proof() {
+        let circuit = Rmd160Circuit::new(b"abc");
+        let instances = circuit.expected_instances::<Fr>();
+        assert!(prove_and_verify(&circuit, &circuit, &instances));
+
+        let wrong = Rmd160Circuit::instances::<Fr>(&rmd160_digest(b"abd"));
+        assert!(!prove_and_verify(&circuit, &circuit, &wrong));
+
+        // a shorter message of the same block count under the keys for "abc"
+        let shorter = Rmd160Circuit::new(b"ab");
+        let instances = shorter.expected_instances::<Fr>();
+        assert!(!prove_and_verify(&circuit, &shorter, &instances));
+    }
+}
@@ -1,3 +1,6 @@
 pub mod rmd160;
 pub mod modexp;
 pub mod range;
+pub mod merkle;
+pub mod bench;
+pub mod message;
@@ -5,30 +5,157 @@ use halo2_proofs::{
     circuit::{Chip, Layouter, Region, AssignedCell, Value},
     plonk::{
         Fixed, Advice, Column, ConstraintSystem,
-        Error, Expression, Selector, VirtualCells
+        Error, Expression, Instance, Selector, TableColumn, VirtualCells
     },
     poly::Rotation,
 };
 
 use std::marker::PhantomData;
 use crate::host::rmd160::{
-    ROUNDS_OFFSET,
-    PROUNDS_OFFSET,
-    R, O, PR, PO,
+    Combination,
+    H0,
+    HashSpec,
+    MERKLE_LEAF_TAG,
+    MERKLE_NODE_TAG,
+    RoundSchedule,
+    Rmd160Spec,
     RMD160Atomic,
+    compress,
+    message_blocks,
+    pad_message,
 };
 use crate::constant;
+use crate::debug_trace;
 
 use crate::utils::{
-    field_to_u64,
+    field_to_u32,
+    field_to_u64_checked,
     u32_to_limbs,
+    u64_to_limbs,
     cell_to_u32,
+    cell_to_u64,
     cell_to_value,
     cell_to_limbs,
+    words_to_digest_bytes,
 };
 
-pub struct RMD160Chip<F: FieldExt> {
+/// rows occupied by one round of either line
+pub const ROWS_PER_ROUND: usize = 17;
+/// rows occupied by the final compress step of a block, the five rows of
+/// the compress gate followed by one u32 word row per digest word
+pub const ROWS_PER_COMPRESS: usize = 10;
+
+/*
+ * The round gate sums four u32 words (a + r + x + offset) inside the field,
+ * so the modulus must exceed 2^34 for the sum not to wrap.
+ * Carries are computed natively in u64 (see carry_split) and never read back
+ * from a field element, thus 64 bit fields such as Goldilocks (p = 2^64 - 2^32 + 1)
+ * are fine as long as they provide a FieldExt implementation.
+ */
+pub const MIN_FIELD_BITS: u32 = 35;
+
+/// Errors of the chip. The hashing methods (assign_content, hash_bytes,
+/// hmac_rmd160, ..) return it, the region level assign methods keep
+/// plonk::Error since they run inside the closures of assign_region.
+/// Both convert into each other, so `?` works in either direction.
+#[derive(Debug)]
+pub enum RMD160Error {
+    FieldTooSmall(u32),
+    /// index of an input word which does not fit in a u32
+    NonCanonicalWord(usize),
+    /// index of a start state word which does not fit in a u32
+    NonCanonicalState(usize),
+    /// an error of the layouter or the constraint system
+    Synthesis(Error),
+    /// the native witness of a step failed the named equation of the
+    /// round gate, round counts the steps of a block: 0 .. 80 for the
+    /// left line and 80 .. 160 for the parallel line
+    WitnessMismatch { round: usize, equation: &'static str },
+    /// an input of this length is not accepted, e.g. an HMAC key longer
+    /// than a block
+    InputLength(usize),
+}
+
+impl From<Error> for RMD160Error {
+    fn from(error: Error) -> Self {
+        RMD160Error::Synthesis(error)
+    }
+}
+
+impl From<RMD160Error> for Error {
+    fn from(error: RMD160Error) -> Self {
+        match error {
+            RMD160Error::Synthesis(error) => error,
+            _ => Error::Synthesis,
+        }
+    }
+}
+
+impl std::fmt::Display for RMD160Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RMD160Error::FieldTooSmall(bits) =>
+                write!(f, "field of {} bits, rmd160 needs at least {}", bits, MIN_FIELD_BITS),
+            RMD160Error::NonCanonicalWord(i) => write!(f, "word {} is not a u32", i),
+            RMD160Error::NonCanonicalState(i) => write!(f, "start state word {} is not a u32", i),
+            RMD160Error::Synthesis(error) => write!(f, "synthesis failed: {}", error),
+            RMD160Error::WitnessMismatch { round, equation } =>
+                write!(f, "witness of round {} fails the {} equation", round, equation),
+            RMD160Error::InputLength(len) => write!(f, "input of {} bytes not accepted", len),
+        }
+    }
+}
+
+impl std::error::Error for RMD160Error {}
+
+fn check_field_bits(num_bits: u32) -> Result<(), RMD160Error> {
+    if num_bits < MIN_FIELD_BITS {
+        Err(RMD160Error::FieldTooSmall(num_bits))
+    } else {
+        Ok(())
+    }
+}
+
+/*
+ * What the chip needs from its field beyond FieldExt: every value below 2^34
+ * is represented exactly and its low 64 bits are the canonical u64 value.
+ * Every FieldExt qualifies by type, the width is a const assertion evaluated
+ * when RMD160Chip::new is instantiated for the field, thus a narrower field
+ * fails the build rather than the synthesis.
+ */
+pub trait RMD160Field: FieldExt {
+    const HOLDS_ROUND_SUM: () = assert!(
+        Self::NUM_BITS >= MIN_FIELD_BITS,
+        "rmd160 chip needs a field holding sums of four u32 words"
+    );
+}
+
+impl<F: FieldExt> RMD160Field for F {}
+
+/// Counters collected during synthesis for profiling layout variants
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SynthesisStats {
+    pub selectors_enabled: usize,
+    pub cells_assigned: usize,
+    pub equality_constraints: usize,
+    /// rows enabled per selector, indexed as the selectors of the config
+    pub selector_rows: [usize; RMD160Config::SELECTORS],
+}
+
+/// The chip only holds its config and the options set by the builder
+/// methods, the assign methods do not mutate it (apart from the counters
+/// of the stats feature and the last witness mismatch). One instance can
+/// thus hash any number of independent messages, each in its own regions,
+/// without a reset.
+pub struct RMD160Chip<F: RMD160Field> {
     config: RMD160Config,
+    witness_assertions: bool,
+    domain_tag: Vec<u8>,
+    /// the last witness mismatch of a round, see block_error
+    mismatch: std::cell::Cell<Option<WitnessMismatch>>,
+    #[cfg(feature = "stats")]
+    stats: std::cell::RefCell<SynthesisStats>,
     _marker: PhantomData<F>,
 }
 
@@ -62,67 +189,260 @@ impl GateCell {
 
 
 /*
- * | h_sel | r_sel | col0| col1  | col2 | col3 | col4 | col5  | col6   |  fix0     |
- * | h_sel | r_sel | a   | b     | c    |  d   | x    | e     | c_next |  offset   |
- * |       |       | w0  | b0    | c0   |  d0  | r0   | w1_h  | w4_h   |  w1_r     |
- * |       |       | wb  | b1    | c1   |  d1  | r1   | w1_l  | w4_l   |  w1_rr    |
- * |       |       | wc  | b2    | c2   |  d2  | r2   | a_next| w2b    |           |
- * |       |       | w1  | b3    | c3   |  d3  | r3   |       | w2c    |           |
- * 
+ * | h_sel | col0| col1  | col2 | col3 | col4 | col5  | col6   |  fix0     |
+ * | h_sel | a   | b     | c    |  d   | x    | e     | c_next |  offset   |
+ * |       | w0  | b0    | c0   |  d0  | r0   | w1_h  | w4_h   |  w1_r     |
+ * |       | wb  | b1    | c1   |  d1  | r1   | w1_l  | w4_l   |  w1_rr    |
+ * |       | wc  | b2    | c2   |  d2  | r2   | a_next| w2b    |  func     |
+ * |       | w1  | b3    | c3   |  d3  | r3   |       | w2c    |           |
+ * |       | l0  | ls0   | h0   |  hs0 | ms0  | k0    | ks0    |           |
+ * |       | l1  | ls1   | h1   |  hs1 | ms1  | k1    | ks1    |           |
+ * |       | l2  | ls2   | h2   |  hs2 | ms2  | k2    | ks2    |           |
+ * |       | l3  | ls3   | h3   |  hs3 | ms3  | k3    | ks3    |           |
+ *
+ * h_sel (selector 0) is enabled on the first row of every round of both
+ * lines and gates all the round gates, which reach the other rows by
+ * rotation. The compress step after the rounds has its own r_sel.
+ *
+ * l, h are the byte limbs of w1_l, w1_h and ls, hs those of w1_l * w1_r
+ * and w1_h * w1_rr. Both a half and the half moved to the top of the word
+ * fit in 32 bits only if the half has the bit width of its side of the split.
+ * The split of c by 10 works the same way with ms, k and ks the limbs of
+ * w4_l * 2^10, w4_h and w4_h * 2^22, w4_l = c - w4_h * 2^22 is an integer
+ * since the limbs of c are range checked.
+ *
+ * |       |       | bn0 | cn0   | dn0  |  rn0 |      |       |        |           |
+ * |       |       | ...                                                           |
+ * |       |       | bn7 | cn7   | dn7  |  rn7 |      |       |        |           |
+ *
+ * bn, cn, dn and rn are the nibbles of b, c, d and r (two per byte limb).
+ * Every (func, bn, cn, dn, rn) is looked up in the table of the boolean
+ * functions, which proves r = f(b, c, d) for the function of the round.
  */
 
-/* All witness we need to fill the gate */
-struct RoundWitness<F: FieldExt> {
+/// All witness we need to fill the round gate of one step, see
+/// precompute_all_witnesses to compute them ahead of the assignment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundWitness<F: FieldExt> {
     r: u32,  // atomic(b, c, d)
     w0: u32, // a + r + x + offset
     wb: F, // a + r + x + offset u64
-    wc: u64, // wb - w0
+    wc: Carry, // (wb - w0) >> 32, within 0..=3
     w1: u32, // w0 rorate_left w1_r
     w1_h: u32,  //w1 >> w1_r
     w1_l: u32,  //w1 % w1_r
     a_next: u32, // w1 + e
     w2b: F, // w1+e u64 
-    w2c: u64, // w2b - a_next
+    w2c: Carry, // (w2b - a_next) >> 32, within 0..=1
     w4_h: u32, // c >> w4_r
     w4_l: u32, // c % w4_r
     c_next: u32, // c rotate_left 10
 }
 
+/* The carry above 2^32 of a round sum. wc carries a + r + x + offset, a sum
+ * of four u32 words, thus within 0..=3; w2c carries w1 + e, a sum of two
+ * words, thus within 0..=1. The round gates check the same bounds, see the
+ * "sum with bound" and "sum with w1 rol4" gates. */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Carry(u64);
+
+impl Carry {
+    const MAX_WC: u64 = 3;
+    const MAX_W2C: u64 = 1;
+
+    fn new(carry: u64, max: u64) -> Self {
+        debug_assert!(carry <= max, "carry {} above its bound {}", carry, max);
+        Carry(carry)
+    }
+
+    fn get(self) -> u64 {
+        self.0
+    }
+}
+
+/* The equation of the round gate a native witness failed to satisfy */
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RoundEquation {
+    /// the byte limbs of r do not sum to r
+    RLimbs,
+    /// wb != a + r + x + offset
+    Sum,
+    /// w2b != w1 + e
+    SumW1,
+    /// a sum does not split into its low word and carry, or does not
+    /// even fit in a u64
+    Carry,
+}
+
+impl RoundEquation {
+    fn name(&self) -> &'static str {
+        match self {
+            RoundEquation::RLimbs => "r limbs",
+            RoundEquation::Sum => "sum",
+            RoundEquation::SumW1 => "sum w1",
+            RoundEquation::Carry => "carry",
+        }
+    }
+}
+
+/* A witness of the round (index) of the left or right (pround) line which
+ * disagrees with the constraints of the gates. Inside a region it can only
+ * be reported as Error::Synthesis, the chip keeps the last mismatch so the
+ * hashing methods return it as RMD160Error::WitnessMismatch. */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct WitnessMismatch {
+    round: usize,
+    index: usize,
+    pround: bool,
+    equation: RoundEquation,
+}
+
+impl From<WitnessMismatch> for Error {
+    fn from(_: WitnessMismatch) -> Error {
+        Error::Synthesis
+    }
+}
+
+impl From<WitnessMismatch> for RMD160Error {
+    fn from(mismatch: WitnessMismatch) -> RMD160Error {
+        RMD160Error::WitnessMismatch {
+            round: mismatch.pround as usize * 80 + mismatch.round * 16 + mismatch.index,
+            equation: mismatch.equation.name(),
+        }
+    }
+}
+
+/* Check the native witness of a round against the sums its gates enforce */
+fn check_round_witness<F: FieldExt>(
+    witness: &RoundWitness<F>,
+    rol: &[u32; 5],
+    x: u32,
+    offset: u32,
+) -> Result<(), RoundEquation> {
+    let rlimbs = u32_to_limbs::<F>(witness.r);
+    let mut sum_r = rlimbs[0];
+    for i in 1..4 {
+        sum_r = sum_r + rlimbs[i] * F::from(1u64 << (8*i));
+    }
+    if sum_r != F::from(witness.r as u64) {
+        return Err(RoundEquation::RLimbs);
+    }
+    if witness.wb != F::from(witness.r as u64) + F::from(rol[0] as u64)
+            + F::from(x as u64) + F::from(offset as u64) {
+        return Err(RoundEquation::Sum);
+    }
+    if witness.w2b != F::from(witness.w1 as u64) + F::from(rol[4] as u64) {
+        return Err(RoundEquation::SumW1);
+    }
+    for (sum, low, carry) in [(&witness.wb, witness.w0, witness.wc), (&witness.w2b, witness.a_next, witness.w2c)] {
+        if field_to_u64_checked(sum).map(carry_split) != Some((low, carry.get())) {
+            return Err(RoundEquation::Carry);
+        }
+    }
+    Ok(())
+}
+
+/* Split a native sum of u32 words into the low word and the carry above 2^32 */
+fn carry_split(sum: u64) -> (u32, u64) {
+    ((sum & 0xffff_ffff) as u32, sum >> 32)
+}
+
+/* The multipliers 2^shift and 2^(32 - shift) of a rotation by shift bits.
+ * They are computed in u64 so that a shift of 0 or 32 neither overflows
+ * nor wraps; any larger shift is not a rotation of a u32 word. */
+fn rotate_multipliers(shift: u32) -> (u64, u64) {
+    assert!(shift <= 32, "rotation by {} bits of a u32 word", shift);
+    (1u64 << shift, 1u64 << (32 - shift))
+}
 
-fn get_witnesses<F: FieldExt>(round: usize, rol: &[u32; 5], x: u32, shift: u32, offset:u32, pround: bool) -> RoundWitness<F> {
-    let f = if pround {5 - round - 1} else { round };
+fn get_witnesses<F: FieldExt, S: HashSpec>(round: usize, rol: &[u32; 5], x: u32, shift: u32, offset:u32, pround: bool) -> RoundWitness<F> {
+    let f = S::function(round, pround);
     let r = u32::atomic(f, rol[1], rol[2], rol[3]);
-    let w0 = r.wrapping_add(rol[0]).wrapping_add(x).wrapping_add(offset);
+    let (w0, wc) = carry_split(r as u64 + rol[0] as u64 + x as u64 + offset as u64);
     let wb = F::from(r as u64) + F::from(rol[0] as u64) + F::from(x as u64) + F::from(offset as u64);
-    let wc = (field_to_u64(&wb) - (w0 as u64)) >> 32;
+    let (w1_r, w1_rr) = rotate_multipliers(shift);
     let w1 = w0.rotate_left(shift);
-    let w1_h = w0 >> (32 - shift);
-    let w1_l = w0 % (2u32.pow(32 - shift));
-    let a_next = w1.wrapping_add(rol[4]);
+    let w1_h = ((w0 as u64 * w1_r) >> 32) as u32;
+    let w1_l = (w0 as u64 % w1_rr) as u32;
+    let (a_next, w2c) = carry_split(w1 as u64 + rol[4] as u64);
     let w2b = F::from(w1 as u64) + F::from(rol[4] as u64);
-    let w2c = (field_to_u64(&w2b) - (a_next as u64)) >> 32;
     let w4_h = rol[2] >> 22;
     let w4_l = rol[2] % (2u32.pow(22));
     let c_next = rol[2].rotate_left(10);
 
-    //println!("round {}, shift {}, offset {} x {}", r, shift ,offset, x);
-    //println!("w2c {}", w2c);
+    debug_trace!("round {}, r {}, shift {}, offset {}, x {}, w2c {}", round, r, shift, offset, x, w2c);
 
     RoundWitness {
-        r, w0, wb, wc, w1, w1_h, w1_l, a_next, w2b, w2c, w4_h, w4_l, c_next
+        r, w0, wb, w1, w1_h, w1_l, a_next, w2b, w4_h, w4_l, c_next,
+        wc: Carry::new(wc, Carry::MAX_WC),
+        w2c: Carry::new(w2c, Carry::MAX_W2C),
     }
 }
 
 
+/* The witness of the step index of round in the left or right (pround) line */
+fn step_witness<F: FieldExt, S: HashSpec>(rol: &[u32; 5], x: u32, round: usize, index: usize, pround: bool) -> RoundWitness<F> {
+    let schedule = RoundSchedule::of::<S>();
+    let shift = schedule.shift(pround, round, index);
+    let offset = schedule.offset(pround, round);
+    get_witnesses::<F, S>(round, rol, x, shift, offset, pround)
+}
+
+/* The witnesses of one block from the native state iv in the order
+ * assign_lines consumes them, the steps of the left line followed by the
+ * steps of the parallel line. Each step needs the state of the previous one
+ * so a line is computed serially. */
+fn block_witnesses<F: FieldExt, S: HashSpec>(iv: &[u32; 5], words: &[u32; 16]) -> Vec<RoundWitness<F>> {
+    let schedule = RoundSchedule::of::<S>();
+    let mut witnesses = vec![];
+    for pround in [false, true] {
+        let mut rol = *iv;
+        for round in 0..schedule.rounds() {
+            for (index, idx) in schedule.order(pround, round).iter().enumerate() {
+                let witness = step_witness::<F, S>(&rol, words[*idx], round, index, pround);
+                rol = [rol[4], witness.a_next, rol[1], witness.c_next, rol[3]];
+                witnesses.push(witness);
+            }
+        }
+    }
+    witnesses
+}
+
+/// the round witnesses of blocks hashed from the start state, H0 for a
+/// whole message or the chaining value of the blocks before, 160 per
+/// block in the order the chip assigns them. Only the chaining values are
+/// computed block after block (by the host compression), the witnesses of
+/// the blocks are then independent and computed with rayon when the
+/// parallel feature is enabled. The output is the same for any number of
+/// threads.
+pub fn precompute_all_witnesses<F: FieldExt>(start: &[u32; 5], blocks: &[[u32; 16]]) -> Vec<RoundWitness<F>> {
+    let mut states = vec![*start];
+    for block in blocks {
+        let state = compress(&states.last().unwrap().to_vec(), block.to_vec());
+        states.push(state.try_into().unwrap());
+    }
+    #[cfg(feature = "parallel")]
+    let witnesses = {
+        use rayon::prelude::*;
+        blocks.par_iter().zip(states.par_iter())
+            .map(|(block, state)| block_witnesses::<F, Rmd160Spec>(state, block))
+            .collect::<Vec<_>>()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let witnesses = blocks.iter().zip(states.iter())
+        .map(|(block, state)| block_witnesses::<F, Rmd160Spec>(state, block))
+        .collect::<Vec<_>>();
+    witnesses.into_iter().flatten().collect()
+}
 
 struct RoundGate ();
 
 impl RoundGate {
     fn hsel(i: usize) -> GateCell { GateCell::sel(0,0, format!("hsel{}", i).as_str()) }
-    fn rsel(i: usize) -> GateCell { GateCell::sel(1,i, format!("hsel{}", i).as_str()) }
     fn offset() -> GateCell { GateCell::fix(0,0, "offset") }
     fn w1_r() -> GateCell { GateCell::fix(0, 1, "w1r") }
     fn w1_rr() -> GateCell { GateCell::fix(0, 2, "w1rr") }
+    fn func() -> GateCell { GateCell::fix(0, 3, "func") }
 
     fn a() -> GateCell { GateCell::adv(0,0, "a") }
     fn w0() -> GateCell { GateCell::adv(0,1, "w0") }
@@ -148,24 +468,46 @@ impl RoundGate {
     fn w4_l() -> GateCell { GateCell::adv(6,2, "w4l") }
     fn w2b() -> GateCell { GateCell::adv(6,3, "w2b") }
     fn w2c() -> GateCell { GateCell::adv(6,4, "w2c") }
+
+    fn w1_l_limb(i: usize) -> GateCell { GateCell::adv(0,i+5, format!("w1l_limb{}",i).as_str()) }
+    fn w1_l_shl_limb(i: usize) -> GateCell { GateCell::adv(1,i+5, format!("w1l_shl_limb{}",i).as_str()) }
+    fn w1_h_limb(i: usize) -> GateCell { GateCell::adv(2,i+5, format!("w1h_limb{}",i).as_str()) }
+    fn w1_h_shl_limb(i: usize) -> GateCell { GateCell::adv(3,i+5, format!("w1h_shl_limb{}",i).as_str()) }
+    fn w4_l_shl_limb(i: usize) -> GateCell { GateCell::adv(4,i+5, format!("w4l_shl_limb{}",i).as_str()) }
+    fn w4_h_limb(i: usize) -> GateCell { GateCell::adv(5,i+5, format!("w4h_limb{}",i).as_str()) }
+    fn w4_h_shl_limb(i: usize) -> GateCell { GateCell::adv(6,i+5, format!("w4h_shl_limb{}",i).as_str()) }
+
+    fn bnibble(i: usize) -> GateCell { GateCell::adv(0,i+9, format!("bnibble{}",i).as_str()) }
+    fn cnibble(i: usize) -> GateCell { GateCell::adv(1,i+9, format!("cnibble{}",i).as_str()) }
+    fn dnibble(i: usize) -> GateCell { GateCell::adv(2,i+9, format!("dnibble{}",i).as_str()) }
+    fn rnibble(i: usize) -> GateCell { GateCell::adv(3,i+9, format!("rnibble{}",i).as_str()) }
 }
 
 
 
 /* Compress sum gate layout
- * | h_sel | r_sel | col0| col1  | col2 | col3 | col4 | col5  | col6 | -- |
- * | h_sel | r_sel | a   | b1    | c2   | sum1 | ca1  | bnew  |      |    |
- * |       |       | b   | c1    | d2   | sum2 | ca2  | cnew  |      |    |
- * |       |       | c   | d1    | e2   | sum3 | ca3  | dnew  |      |    |
- * |       |       | d   | e1    | a2   | sum4 | ca4  | enew  |      |    |
- * |       |       | e   | a1    | b2   | sum5 | ca5  | anew  |      |    |
- * 
+ * | r_sel | col0| col1  | col2 | col3 | col4 | col5  | col6 | -- |
+ * | r_sel | a   | b1    | c2   | sum1 | ca1  | bnew  |      |    |
+ * |       | b   | c1    | d2   | sum2 | ca2  | cnew  |      |    |
+ * |       | c   | d1    | e2   | sum3 | ca3  | dnew  |      |    |
+ * |       | d   | e1    | a2   | sum4 | ca4  | enew  |      |    |
+ * |       | e   | a1    | b2   | sum5 | ca5  | anew  |      |    |
+ * | u_sel | bnew  | ... the u32 word rows of bnew, cnew, dnew, enew, anew
+ *
+ * r_sel (selector 1) is enabled on the first row of the compress step of
+ * every block, right after the rows of its 160 rounds, and gates the
+ * compress gate only. h_sel is never enabled on these rows.
+ *
+ * The compress gate only ties new + ca * 2^32 to the sum, with ca one of
+ * 0, 1 and 2: a carry too small by one is matched by new = sum mod 2^32
+ * plus 2^32. The five rows after it bound every new word to u32 (see the
+ * u32 word layout), which leaves the true carry as the only choice.
  */
 
 struct CompressGate ();
 
 impl CompressGate {
-    fn rsel(i: usize) -> GateCell { GateCell::sel(1,i, format!("hsel{}", i).as_str()) }
+    fn rsel(i: usize) -> GateCell { GateCell::sel(1,i, format!("rsel{}", i).as_str()) }
     fn a() -> GateCell { GateCell::adv(0, 0, "a") }
     fn b() -> GateCell { GateCell::adv(0, 1, "b") }
     fn c() -> GateCell { GateCell::adv(0, 2, "c") }
@@ -196,23 +538,559 @@ impl CompressGate {
     fn ca3() -> GateCell { GateCell::adv(4, 3, "ca3") }
     fn ca4() -> GateCell { GateCell::adv(4, 4, "ca4") }
 
-    fn bnew() -> GateCell { GateCell::adv(4, 0, "bnew") }
-    fn cnew() -> GateCell { GateCell::adv(4, 1, "cnew") }
-    fn dnew() -> GateCell { GateCell::adv(4, 2, "dnew") }
-    fn enew() -> GateCell { GateCell::adv(4, 3, "enew") }
-    fn anew() -> GateCell { GateCell::adv(4, 4, "anew") }
+    fn bnew() -> GateCell { GateCell::adv(5, 0, "bnew") }
+    fn cnew() -> GateCell { GateCell::adv(5, 1, "cnew") }
+    fn dnew() -> GateCell { GateCell::adv(5, 2, "dnew") }
+    fn enew() -> GateCell { GateCell::adv(5, 3, "enew") }
+    fn anew() -> GateCell { GateCell::adv(5, 4, "anew") }
+
+    /// the cells [r0, r1, r2, sum, ca, new] of lane i, which adds the
+    /// words r0[i], r1[i+1] and r2[i+2] and outputs digest word i-1
+    fn lane(i: usize) -> [GateCell; 6] {
+        [
+            GateCell::adv(0, i, format!("r0 lane{}", i).as_str()),
+            GateCell::adv(1, i, format!("r1 lane{}", i).as_str()),
+            GateCell::adv(2, i, format!("r2 lane{}", i).as_str()),
+            GateCell::adv(3, i, format!("sum{}", i).as_str()),
+            GateCell::adv(4, i, format!("ca{}", i).as_str()),
+            GateCell::adv(5, (i + 4) % 5, format!("new lane{}", i).as_str()),
+        ]
+    }
+}
+
+/* Digest packing layout
+ * | p_sel | col0 | col1 | col2 | col3 | col4 | col5 |
+ * | p_sel | w0   | w1   | w2   | w3   | w4   | low  |
+ * | u_sel | w0   | ... the u32 word rows of w0 .. w4
+ *
+ * low = w0 + w1 * 2^32 + w2 * 2^64 + w3 * 2^96 holds the first 16 digest bytes
+ * and w4 the last 4, both read as little endian integers.
+ */
+struct PackGate ();
+
+impl PackGate {
+    fn psel() -> GateCell { GateCell::sel(2, 0, "psel") }
+    fn w(i: usize) -> GateCell { GateCell::adv(i, 0, format!("w{}", i).as_str()) }
+    fn low() -> GateCell { GateCell::adv(5, 0, "low") }
+}
+
+/* Length field layout
+ * | l_sel | col0 | col1 | col2 | col3 | col4 | col5 | col6 |
+ * | l_sel | len  | lo   | hi   | l0   | l1   | l2   | l3   |
+ * |       |      |      |      | l4   | l5   | l6   | l7   |
+ *
+ * lo and hi are the words 14 and 15 of the last block and
+ * l0 .. l7 the little endian bytes of the bit length len * 8.
+ */
+struct LengthGate ();
+
+impl LengthGate {
+    fn lsel() -> GateCell { GateCell::sel(3, 0, "lsel") }
+    fn len() -> GateCell { GateCell::adv(0, 0, "len") }
+    fn lo() -> GateCell { GateCell::adv(1, 0, "lo") }
+    fn hi() -> GateCell { GateCell::adv(2, 0, "hi") }
+    fn limb(i: usize) -> GateCell { GateCell::adv(3 + i % 4, i / 4, format!("l{}", i).as_str()) }
+}
+
+/* Padding layout, a header row followed by one row per byte of the padded blocks
+ * | selectors                     | col0 | col1 | col2 | col3 | fix0 |
+ * | head, step                    | 0    | 1    | 0    |      |      |
+ * | step, body, word              | b0   | m0   | cnt0 | w0   |      |
+ * | step, body                    | b1   | m1   | cnt1 |      |      |
+ * | step, body                    | b2   | m2   | cnt2 |      |      |
+ * | step, body                    | b3   | m3   | cnt3 |      |      |
+ * | ...                           |      |      |      |      |      |
+ * | bound                         | bj   | mj   | cntj |      | flag |
+ *
+ * m is 1 on message bytes and 0 afterwards, cnt counts the message bytes.
+ * The byte after the message (m - m_next == 1) must be 0x80 and every other
+ * non message byte before the length field must be 0. The bound rows pin the
+ * flag right before the length field to 0 and, for messages spanning several
+ * blocks, the flag 72 bytes before the end to 1 so that the padding is minimal.
+ */
+struct PadGate ();
+
+impl PadGate {
+    fn head() -> GateCell { GateCell::sel(4, 0, "pad_head") }
+    fn step() -> GateCell { GateCell::sel(5, 0, "pad_step") }
+    fn body() -> GateCell { GateCell::sel(6, 0, "pad_body") }
+    fn word() -> GateCell { GateCell::sel(7, 0, "pad_word") }
+    fn bound() -> GateCell { GateCell::sel(8, 0, "pad_bound") }
+    fn flag() -> GateCell { GateCell::fix(0, 0, "flag") }
+
+    fn b() -> GateCell { GateCell::adv(0, 0, "b") }
+    fn m() -> GateCell { GateCell::adv(1, 0, "m") }
+    fn cnt() -> GateCell { GateCell::adv(2, 0, "cnt") }
+    fn w() -> GateCell { GateCell::adv(3, 0, "w") }
+    fn b_next() -> GateCell { GateCell::adv(0, 1, "b_next") }
+    fn m_next() -> GateCell { GateCell::adv(1, 1, "m_next") }
+    fn cnt_next() -> GateCell { GateCell::adv(2, 1, "cnt_next") }
+    fn byte(i: usize) -> GateCell { GateCell::adv(0, i, format!("byte{}", i).as_str()) }
+}
+
+/* Constants of a pair block
+ * | pair_sel | col0 | col1 | col2 | col3 | col4 | col5 |
+ * | pair_sel | w10  | w11  | w12  | w13  | w14  | w15  |
+ * |          | h0   | h1   | h2   | h3   | h4   |      |
+ *
+ * Two digests fill the words 0 .. 9 of a single block, w10 .. w15 are the
+ * padding of a 40 byte message and h0 .. h4 the initial state.
+ */
+struct PairGate ();
+
+impl PairGate {
+    fn pair() -> GateCell { GateCell::sel(9, 0, "pair_sel") }
+    fn tail(i: usize) -> GateCell { GateCell::adv(i, 0, format!("w{}", i + 10).as_str()) }
+    fn iv(i: usize) -> GateCell { GateCell::adv(i, 1, format!("h{}", i).as_str()) }
+}
+
+/// words 10 .. 15 of the block hashing two concatenated digests
+const PAIR_TAIL: [u32; 6] = [0x80, 0, 0, 0, 40 * 8, 0];
+
+/* Conditional swap layout, one row per digest word
+ * | swap_sel | col0 | col1 | col2 | col3 | col4 |
+ * | swap_sel | s    | a    | b    | l    | r    |
+ *
+ * s is boolean, (l, r) = (a, b) when s = 0 and (b, a) when s = 1.
+ */
+struct SwapGate ();
+
+impl SwapGate {
+    fn swap() -> GateCell { GateCell::sel(10, 0, "swap_sel") }
+    fn s() -> GateCell { GateCell::adv(0, 0, "s") }
+    fn a() -> GateCell { GateCell::adv(1, 0, "a") }
+    fn b() -> GateCell { GateCell::adv(2, 0, "b") }
+    fn l() -> GateCell { GateCell::adv(3, 0, "l") }
+    fn r() -> GateCell { GateCell::adv(4, 0, "r") }
+}
+
+/* HMAC key layout, two rows per key byte
+ * | hmac_key | col0 | col1 | col2 | col3 | col4 | col5 | col6 |
+ * | hmac_key | k    | ipad | opad | k0   | k1   | k2   | k3   |
+ * |          | k4   | k5   | k6   | k7   |      |      |      |
+ *
+ * k0 .. k7 are the bits of the key byte k, ipad = k ^ 0x36 and
+ * opad = k ^ 0x5c are recombined from the bits flipped by the constants.
+ */
+struct HmacGate ();
+
+impl HmacGate {
+    fn key() -> GateCell { GateCell::sel(11, 0, "hmac_key") }
+    fn k() -> GateCell { GateCell::adv(0, 0, "k") }
+    fn ipad() -> GateCell { GateCell::adv(1, 0, "ipad") }
+    fn opad() -> GateCell { GateCell::adv(2, 0, "opad") }
+    fn bit(i: usize) -> GateCell {
+        if i < 4 {
+            GateCell::adv(3 + i, 0, format!("k{}", i).as_str())
+        } else {
+            GateCell::adv(i - 4, 1, format!("k{}", i).as_str())
+        }
+    }
+}
+
+/* Bit packing layout, five rows per message word
+ * | bits_sel | col0 | col1 | col2 | col3 | col4 | col5 | col6 |
+ * | bits_sel | w    | x0   | x1   | x2   | x3   | x4   | x5   |
+ * |          | x6   | x7   | x8   | x9   | x10  | x11  | x12  |
+ * |          | ...  |      |      |      |      |      |      |
+ * |          | x27  | x28  | x29  | x30  | x31  |      |      |
+ *
+ * x0 .. x31 are boolean and w = x0 + x1 * 2 + .. + x31 * 2^31.
+ */
+struct BitsGate ();
+
+impl BitsGate {
+    fn bits() -> GateCell { GateCell::sel(12, 0, "bits_sel") }
+    fn w() -> GateCell { GateCell::adv(0, 0, "w") }
+    fn bit(i: usize) -> GateCell { GateCell::adv((i + 1) % 7, (i + 1) / 7, format!("x{}", i).as_str()) }
+}
+
+/* u32 word layout, one row per word
+ * | u_sel | col0 | col1 | col2 | col3 | col4 |
+ * | u_sel | w    | l0   | l1   | l2   | l3   |
+ *
+ * l0 .. l3 are the little endian bytes of w, each looked up in the byte
+ * range table, thus w < 2^32. w is a copy of the word it bounds.
+ */
+struct WordGate ();
+
+impl WordGate {
+    fn word() -> GateCell { GateCell::sel(13, 0, "u_sel") }
+    fn w() -> GateCell { GateCell::adv(0, 0, "w") }
+    fn limb(i: usize) -> GateCell { GateCell::adv(1 + i, 0, format!("l{}", i).as_str()) }
+}
+
+/// the inner and outer pad bytes of HMAC and its block size in bytes
+pub const HMAC_IPAD: u8 = 0x36;
+pub const HMAC_OPAD: u8 = 0x5c;
+pub const HMAC_BLOCK_LEN: usize = 64;
+
+/// The carry cells of one block. wc and w2c hold the carries of the two
+/// sums of every round, 80 rounds of the left line followed by 80 rounds of
+/// the parallel line, and compress holds the carry of each digest word.
+#[derive(Clone, Debug)]
+pub struct BlockCarries<F: FieldExt> {
+    pub wc: Vec<AssignedCell<F, F>>,
+    pub w2c: Vec<AssignedCell<F, F>>,
+    pub compress: [AssignedCell<F, F>; 5],
+}
+
+/// The five assigned words of a digest, in RIPEMD-160 output order
+#[derive(Clone, Debug)]
+pub struct AssignedDigest<F: FieldExt> {
+    cells: [AssignedCell<F, F>; 5],
+}
+
+impl<F: FieldExt> AssignedDigest<F> {
+    pub fn from_cells(cells: [AssignedCell<F, F>; 5]) -> Self {
+        AssignedDigest { cells }
+    }
+
+    pub fn cells(&self) -> &[AssignedCell<F, F>; 5] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> [AssignedCell<F, F>; 5] {
+        self.cells
+    }
+
+    /// the digest bytes, each word is serialized in little endian
+    pub fn bytes(&self) -> [u8; 20] {
+        words_to_digest_bytes(std::array::from_fn(|i| cell_to_u32(&self.cells[i])))
+    }
+
+    /// constrain the words to consecutive rows of the instance column
+    /// starting at instance_offset
+    pub fn verify_against_instance(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        instance: Column<Instance>,
+        instance_offset: usize,
+    ) -> Result<(), Error> {
+        for (i, cell) in self.cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), instance, instance_offset + i)?;
+        }
+        Ok(())
+    }
+
+    /// constrain the words to be equal to the words of another digest
+    pub fn constrain_equal(
+        &self,
+        region: &mut Region<F>,
+        other: &AssignedDigest<F>,
+    ) -> Result<(), Error> {
+        for (a, b) in self.cells.iter().zip(other.cells.iter()) {
+            region.constrain_equal(a.cell(), b.cell())?;
+        }
+        Ok(())
+    }
+}
+
+/// Incremental hashing on top of RMD160Chip: each update compresses one
+/// block (already padded, see pad_message) into the running chaining value,
+/// which is kept as assigned cells and copy constrained into the region of
+/// the next block. The chaining value starts at the constant H0.
+pub struct RMD160Hasher<'a, F: RMD160Field> {
+    chip: &'a RMD160Chip<F>,
+    state: Option<[AssignedCell<F, F>; 5]>,
+}
+
+impl<'a, F: RMD160Field> RMD160Hasher<'a, F> {
+    pub fn new(chip: &'a RMD160Chip<F>) -> Self {
+        RMD160Hasher { chip, state: None }
+    }
+
+    /// resume from a chaining value assigned elsewhere instead of H0
+    pub fn with_state(chip: &'a RMD160Chip<F>, state: [AssignedCell<F, F>; 5]) -> Self {
+        RMD160Hasher { chip, state: Some(state) }
+    }
+
+    pub fn update(
+        &mut self,
+        layouter: &mut impl Layouter<F>,
+        block: &[AssignedCell<F, F>; 16],
+    ) -> Result<(), RMD160Error> {
+        let state = match self.state.take() {
+            Some(state) => state,
+            None => self.chip.default_state(layouter)?,
+        };
+        self.state = Some(self.chip.assign_content(layouter, &state, block)?);
+        Ok(())
+    }
+
+    /// the chaining value after the last update, H0 if nothing was hashed
+    pub fn finalize(self, layouter: &mut impl Layouter<F>) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        match self.state {
+            Some(state) => Ok(state),
+            None => Ok(self.chip.default_state(layouter)?),
+        }
+    }
+}
+
+/// the 20 digest bytes of the assigned words, the inverse of the packing of
+/// RMD160Chip::assign_bytes_block: each word is serialized in little endian
+pub fn digest_to_bytes<F: FieldExt>(digest: &[AssignedCell<F, F>; 5]) -> [u8; 20] {
+    AssignedDigest::from_cells(digest.clone()).bytes()
+}
+
+/// names of the gates in the order they are created by RMD160Chip::configure,
+/// the gate index of a MockProver failure (counted from the first rmd160 gate)
+/// indexes this list
+const GATE_NAMES: [&str; 21] = [
+    "sum with bound",
+    "sum with w1 rol4",
+    "limbs sum",
+    "c rotate",
+    "w0 rotate",
+    "w1 split",
+    "w4 split",
+    "nibbles sum",
+    "compress",
+    "digest pack",
+    "length field",
+    "padding head",
+    "padding step",
+    "padding marker",
+    "padding word",
+    "padding bound",
+    "pair constants",
+    "conditional swap",
+    "hmac key",
+    "bits pack",
+    "u32 word",
+];
+
+/// the selector gating each gate of GATE_NAMES, in the same order
+#[cfg(feature = "stats")]
+const GATE_SELECTORS: [usize; 21] = [
+    0, 0, 0, 0, 0, 0, 0, 0, // the round gates, h_sel
+    1,                      // compress, r_sel
+    2,                      // digest pack
+    3,                      // length field
+    4, 5, 6, 7, 8,          // padding head, step, marker, word and bound
+    9,                      // pair constants
+    10,                     // conditional swap
+    11,                     // hmac key
+    12,                     // bits pack
+    13,                     // u32 word
+];
+
+/// A lookup table of the 8 bit values 0 .. 256. It is created once per
+/// circuit and handed to every chip that looks up bytes in it, so several
+/// chips share the same rows.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeTable {
+    col: TableColumn,
+}
+
+impl RangeTable {
+    pub const BITS: usize = 8;
+    pub const COLUMNS: usize = 1;
+    pub const ROWS: usize = 1 << Self::BITS;
+
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>) -> Self {
+        RangeTable { col: cs.lookup_table_column() }
+    }
+
+    /// fill the table, has to happen once in the synthesize of the circuit
+    pub fn load<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "range table",
+            |mut table| {
+                for i in 0..1 << Self::BITS {
+                    table.assign_cell(|| "range", self.col, i, || Value::known(F::from(i as u64)))?;
+                }
+                Ok(())
+            }
+        )
+    }
+
+    pub fn column(&self) -> TableColumn {
+        self.col
+    }
+}
+
+/// A lookup table of the five boolean round functions on nibbles, rows
+/// (f, x, y, z, f(x, y, z)) for every f and every three nibbles. With its
+/// 5 * 16^3 rows it is by far the largest table of the chip, thus a circuit
+/// using the chip needs k >= MIN_K whatever the number of blocks. Like
+/// RangeTable it is created once and shared by the chips using it.
+#[derive(Clone, Copy, Debug)]
+pub struct RoundFunctionTable {
+    function: [TableColumn; 5],
+}
+
+impl RoundFunctionTable {
+    pub const NIBBLE_BITS: usize = 4;
+    /// the lookup columns: the function index, the three nibbles and f
+    pub const COLUMNS: usize = 5;
+    pub const ROWS: usize = 5 << (3 * Self::NIBBLE_BITS);
+    /// the smallest k whose 2^k rows hold the table, with room to spare
+    /// for the blinding rows
+    pub const MIN_K: u32 = usize::BITS - Self::ROWS.leading_zeros();
+
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>) -> Self {
+        RoundFunctionTable { function: [0; 5].map(|_| cs.lookup_table_column()) }
+    }
+
+    /// fill the table, has to happen once in the synthesize of the circuit
+    pub fn load<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "round function table",
+            |mut table| {
+                let n = 1u32 << Self::NIBBLE_BITS;
+                let mut row = 0;
+                for f in 0..5 {
+                    for x in 0..n {
+                        for y in 0..n {
+                            for z in 0..n {
+                                let r = u32::atomic(f, x, y, z) & (n - 1);
+                                for (col, v) in self.function.iter().zip([f as u32, x, y, z, r]) {
+                                    table.assign_cell(|| "round function", *col, row, || Value::known(F::from(v as u64)))?;
+                                }
+                                row += 1;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+        )
+    }
+
+    pub fn columns(&self) -> [TableColumn; 5] {
+        self.function
+    }
+}
+
+/// A lookup table of the 16 bit values 0 .. 2^16. One lookup against it
+/// replaces two byte lookups, at the price of 2^16 table rows (thus k >= 17).
+/// Like RangeTable it is created once and shared by the chips using it.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeTableConfig {
+    col: TableColumn,
+}
+
+impl RangeTableConfig {
+    pub const BITS: usize = 16;
+
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>) -> Self {
+        RangeTableConfig { col: cs.lookup_table_column() }
+    }
+
+    pub fn load<F: FieldExt>(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "16 bit range table",
+            |mut table| {
+                for i in 0..1 << Self::BITS {
+                    table.assign_cell(|| "range", self.col, i, || Value::known(F::from(i as u64)))?;
+                }
+                Ok(())
+            }
+        )
+    }
+
+    /// constrain the expression to 0 .. 2^16, the expression has to be
+    /// multiplied by its selector so that disabled rows look up 0
+    pub fn lookup_range<F: FieldExt>(
+        &self,
+        cs: &mut ConstraintSystem<F>,
+        name: &'static str,
+        expr: impl FnOnce(&mut VirtualCells<F>) -> Expression<F>,
+    ) {
+        cs.lookup(name, |meta| vec![(expr(meta), self.col)]);
+    }
+
+    pub fn column(&self) -> TableColumn {
+        self.col
+    }
+}
+
+/// The column and row footprint of the chip, see RMD160Chip::layout_info
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutInfo {
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub selectors: usize,
+    /// lookup table columns of the RangeTable and the RoundFunctionTable,
+    /// shared by every chip using the same tables
+    pub table_columns: usize,
+    /// rows the largest table occupies, k must leave room for them and the
+    /// blinding rows
+    pub table_rows: usize,
+    /// the smallest k of a circuit using the chip, set by the table rows
+    pub min_k: u32,
+    pub rows_per_round: usize,
+    pub rows_per_compress: usize,
 }
 
+/// render the layout of circuit at k to an SVG file, limited to rows so
+/// that a few rounds (ROWS_PER_ROUND rows each) stay readable. Helps to
+/// pick k and to spot unused cells or overlapping regions.
+#[cfg(feature = "dev-graph")]
+pub fn render_layout_svg<F: FieldExt, C: halo2_proofs::plonk::Circuit<F>>(
+    k: u32,
+    circuit: &C,
+    rows: std::ops::Range<usize>,
+    path: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use plotters::prelude::*;
+    let root = SVGBackend::new(path, (1024, 3096)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let root = root.titled("RMD160 Layout", ("sans-serif", 60))?;
+    halo2_proofs::dev::CircuitLayout::default()
+        .view_height(rows)
+        .show_labels(true)
+        .render(k, circuit, &root)?;
+    root.present()?;
+    Ok(())
+}
+
+/// advice columns used by the round and compress layouts (col0 .. col6)
+pub const WITNESS_COLS: usize = 7;
+
 #[derive(Clone, Debug)]
 pub struct RMD160Config {
-    witness: [Column<Advice>; 7],
-    selector: [Selector; 2],
+    witness: [Column<Advice>; WITNESS_COLS],
+    selector: [Selector; 14],
     fixed: [Column<Fixed>; 1],
+    range: RangeTable,
+    functions: RoundFunctionTable,
+    range_check: bool,
+    instance: Option<Column<Instance>>,
+    wide_range: Option<RangeTableConfig>,
 }
 
 impl RMD160Config {
+    pub const WITNESS_COLS: usize = WITNESS_COLS;
+    pub const FIXED_COLS: usize = 1;
+    pub const SELECTORS: usize = 14;
+
+    /// the byte range table the lookups of this config are registered against
+    pub fn range_table(&self) -> RangeTable {
+        self.range
+    }
+
+    /// the round function table the lookups of this config are registered against
+    pub fn round_function_table(&self) -> RoundFunctionTable {
+        self.functions
+    }
+
+    /// whether the range lookups were registered, see RMD160ConfigBuilder
+    pub fn range_check_enabled(&self) -> bool {
+        self.range_check
+    }
+
+    /// the instance column of the public digest, see RMD160ConfigBuilder
+    pub fn instance(&self) -> Option<Column<Instance>> {
+        self.instance
+    }
+
     fn get_expr<F:FieldExt>(&self, meta: &mut VirtualCells<F>, gate_cell: GateCell) -> Expression<F> {
         let cell = gate_cell.cell;
+        debug_assert!(cell[0] != 0 || cell[1] < WITNESS_COLS,
+            "gate cell {} queries advice column {} but only {} are configured",
+            gate_cell.to_string(), cell[1], WITNESS_COLS);
         if cell[0] == 0 { // advice
             meta.query_advice(self.witness[cell[1]], Rotation(cell[2] as i32))
         } else if cell[0] == 1 { // fix
@@ -223,7 +1101,83 @@ impl RMD160Config {
     }
 }
 
-impl<F: FieldExt> Chip<F> for RMD160Chip<F> {
+// the gate cells above hard code the columns 0..=6, keep them in sync with the config
+const _: () = assert!(RMD160Config::WITNESS_COLS == 7);
+
+/// Options applied when the gates of RMD160Chip are configured.
+///
+/// enable_range_check (on by default) registers every lookup against the
+/// byte range table and the round function table. Turning it off makes the
+/// circuit smaller and faster but UNSOUND: the limbs and bytes of the
+/// witness are no longer bounded and r is no longer tied to b, c and d,
+/// so a prover can satisfy the gates with a wrong digest. Only use it for
+/// tests or benchmarks of the arithmetic gates.
+///
+/// enable_public_digest (off by default) adds an instance column with
+/// equality enabled, RMD160Chip::constrain_digest_public binds a digest to it.
+///
+/// wide_range_table looks up the limbs of b, c and d two bytes at a time in
+/// a 16 bit table instead of one byte at a time: 6 lookups per round instead
+/// of 12. The single bytes stay bounded by their nibbles, which the round
+/// function table checks.
+#[derive(Clone, Copy, Debug)]
+pub struct RMD160ConfigBuilder {
+    enable_range_check: bool,
+    enable_public_digest: bool,
+    wide_range: Option<RangeTableConfig>,
+}
+
+impl Default for RMD160ConfigBuilder {
+    fn default() -> Self {
+        RMD160ConfigBuilder { enable_range_check: true, enable_public_digest: false, wide_range: None }
+    }
+}
+
+impl RMD160ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable_range_check(mut self, enabled: bool) -> Self {
+        self.enable_range_check = enabled;
+        self
+    }
+
+    pub fn enable_public_digest(mut self, enabled: bool) -> Self {
+        self.enable_public_digest = enabled;
+        self
+    }
+
+    pub fn wide_range_table(mut self, table: RangeTableConfig) -> Self {
+        self.wide_range = Some(table);
+        self
+    }
+
+    /// same as RMD160Chip::configure with the options of the builder
+    pub fn configure<F: RMD160Field>(self, cs: &mut ConstraintSystem<F>) -> RMD160Config {
+        let witness= [0; RMD160Config::WITNESS_COLS]
+                .map(|_|cs.advice_column());
+        let fixed= [0; RMD160Config::FIXED_COLS]
+                .map(|_|cs.fixed_column());
+        let range = RangeTable::configure(cs);
+        let functions = RoundFunctionTable::configure(cs);
+        self.configure_with(cs, &witness, &fixed, &range, &functions)
+    }
+
+    /// same as RMD160Chip::configure_with with the options of the builder
+    pub fn configure_with<F: RMD160Field>(
+        self,
+        cs: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        fixed: &[Column<Fixed>],
+        range: &RangeTable,
+        functions: &RoundFunctionTable,
+    ) -> RMD160Config {
+        RMD160Chip::<F>::configure_with_options(cs, advice, fixed, range, functions, self)
+    }
+}
+
+impl<F: RMD160Field> Chip<F> for RMD160Chip<F> {
     type Config = RMD160Config;
     type Loaded = ();
 
@@ -236,41 +1190,307 @@ impl<F: FieldExt> Chip<F> for RMD160Chip<F> {
     }
 }
 
-impl<F: FieldExt> RMD160Chip<F> {
+impl<F: RMD160Field> RMD160Chip<F> {
     pub fn new(config: RMD160Config) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = F::HOLDS_ROUND_SUM;
         RMD160Chip {
             config,
+            witness_assertions: true,
+            domain_tag: vec![],
+            mismatch: std::cell::Cell::new(None),
+            #[cfg(feature = "stats")]
+            stats: std::cell::RefCell::new(SynthesisStats::default()),
             _marker: PhantomData,
         }
     }
 
-    pub fn configure(cs: &mut ConstraintSystem<F>) -> RMD160Config {
-        let witness= [0; 7]
-                .map(|_|cs.advice_column());
-        let fixed= [0; 1]
-                .map(|_|cs.fixed_column());
-        let selector= [0; 2]
-                .map(|_|cs.selector());
-        witness.map(|x| cs.enable_equality(x));
+    /// the error of a failed block region: the witness mismatch recorded
+    /// by the round assignment if there is one, the plonk error otherwise
+    fn block_error(&self, error: Error) -> RMD160Error {
+        match self.mismatch.take() {
+            Some(mismatch) => mismatch.into(),
+            None => error.into(),
+        }
+    }
 
-        let config = RMD160Config { fixed, selector, witness };
+    /// fill the byte range table and the round function table of the config.
+    /// This has to be called once in synthesize (once for all the chips
+    /// sharing the tables) before any padding or length is assigned,
+    /// otherwise the lookups fail.
+    pub fn load_range_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.config.range.load(layouter)?;
+        self.config.functions.load(layouter)?;
+        match self.config.wide_range {
+            Some(wide) => wide.load(layouter),
+            None => Ok(()),
+        }
+    }
 
-        cs.create_gate("sum with bound", |meta| {
-            let mut sum_r = config.get_expr(meta, RoundGate::rlimb(0));
-            for i in 1..4 {
-                let limb = config.get_expr(meta, RoundGate::rlimb(i));
-                sum_r = sum_r + limb * F::from(1u64 << (8*i));
-            }
-            let w0 = config.get_expr(meta, RoundGate::w0());
-            let wb = config.get_expr(meta, RoundGate::wb());
-            let wc = config.get_expr(meta, RoundGate::wc());
-            let a = config.get_expr(meta, RoundGate::a());
-            let x = config.get_expr(meta, RoundGate::x());
-            let offset = config.get_expr(meta, RoundGate::offset());
-            let hsel = config.get_expr(meta, RoundGate::hsel(0));
-            vec![
-                (wb.clone() - sum_r - a - x - offset) * hsel.clone(),
-                //(wc.clone()*(wc.clone() - constant!(F::one()))) * hsel.clone(),
+    /// toggle the native sanity checks on the computed witness (on by default).
+    /// A failed check aborts the synthesis, the hashing methods report it as
+    /// RMD160Error::WitnessMismatch; turning them off leaves the gates unchanged.
+    pub fn with_witness_assertions(mut self, enabled: bool) -> Self {
+        self.witness_assertions = enabled;
+        self
+    }
+
+    /// prepend the tag to every message hashed from bytes by this chip,
+    /// the tag bytes are constrained to constants (empty by default)
+    pub fn with_domain_tag(mut self, tag: &[u8]) -> Self {
+        self.domain_tag = tag.to_vec();
+        self
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> SynthesisStats {
+        *self.stats.borrow()
+    }
+
+    /// floor planners may run a region twice, reset before the pass to measure
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&self) {
+        *self.stats.borrow_mut() = SynthesisStats::default();
+    }
+
+    /// the gates whose selector was not enabled on any row since the last
+    /// reset_stats: their constraints never apply in what was synthesized
+    #[cfg(feature = "stats")]
+    pub fn idle_gates(&self) -> Vec<&'static str> {
+        let stats = self.stats.borrow();
+        GATE_NAMES.iter().zip(GATE_SELECTORS)
+            .filter(|(_, selector)| stats.selector_rows[*selector] == 0)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// constrain the digest words to the rows 0 .. 5 of the instance column
+    /// of the config, thus the public inputs are [h0, h1, h2, h3, h4] where
+    /// each word is the u32 read in little endian from the digest bytes
+    /// 4i .. 4i + 4. Fails with Error::Synthesis if the config was built
+    /// without enable_public_digest.
+    pub fn constrain_digest_public(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        digest: &[AssignedCell<F, F>; 5],
+    ) -> Result<(), Error> {
+        let instance = self.config.instance.ok_or(Error::Synthesis)?;
+        AssignedDigest::from_cells(digest.clone()).verify_against_instance(layouter, instance, 0)
+    }
+
+    /// make sure the field is wide enough for the carry logic of the gates
+    pub fn check_field() -> Result<(), RMD160Error> {
+        check_field_bits(F::NUM_BITS)
+    }
+
+    /// reject a start state or message words which are not canonical u32,
+    /// a start state word is reported as NonCanonicalState
+    fn check_state(
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<(), RMD160Error> {
+        Self::check_words(&std::array::from_fn::<F, 5, _>(|i| cell_to_value(&start_buf[i])))
+            .map_err(|error| match error {
+                RMD160Error::NonCanonicalWord(i) => RMD160Error::NonCanonicalState(i),
+                error => error,
+            })?;
+        Self::check_words(&std::array::from_fn::<F, 16, _>(|i| cell_to_value(&inputs[i])))
+    }
+
+    /// make sure every message word is a canonical u32
+    pub fn check_words(words: &[F]) -> Result<(), RMD160Error> {
+        for (i, w) in words.iter().enumerate() {
+            if F::from(field_to_u32(w) as u64) != *w {
+                return Err(RMD160Error::NonCanonicalWord(i));
+            }
+        }
+        Ok(())
+    }
+
+    /// the names of the gates created by configure, in registration order
+    pub fn gate_names() -> &'static [&'static str] {
+        &GATE_NAMES
+    }
+
+    /// same as configure but rejects fields too small to hold the round sums
+    pub fn try_configure(cs: &mut ConstraintSystem<F>) -> Result<RMD160Config, RMD160Error> {
+        Self::check_field()?;
+        Ok(Self::configure(cs))
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<F>) -> RMD160Config {
+        RMD160ConfigBuilder::default().configure(cs)
+    }
+
+    /// configure the gates on columns provided by the caller, so an outer
+    /// circuit can share its column budget with the chip. The first
+    /// WITNESS_COLS advice and FIXED_COLS fixed columns are used, equality
+    /// is enabled on the advice columns and the fixed column holds constants.
+    /// The byte and round function lookups are registered against the
+    /// shared tables.
+    pub fn configure_with(
+        cs: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        fixed: &[Column<Fixed>],
+        range: &RangeTable,
+        functions: &RoundFunctionTable,
+    ) -> RMD160Config {
+        Self::configure_with_options(cs, advice, fixed, range, functions, RMD160ConfigBuilder::default())
+    }
+
+    fn configure_with_options(
+        cs: &mut ConstraintSystem<F>,
+        advice: &[Column<Advice>],
+        fixed: &[Column<Fixed>],
+        range: &RangeTable,
+        functions: &RoundFunctionTable,
+        options: RMD160ConfigBuilder,
+    ) -> RMD160Config {
+        debug_assert!(F::NUM_BITS >= MIN_FIELD_BITS,
+            "rmd160 carries need the field to hold sums of four u32 words (up to 2^34), field has {} bits",
+            F::NUM_BITS);
+        assert!(advice.len() >= RMD160Config::WITNESS_COLS,
+            "rmd160 needs {} advice columns, got {}", RMD160Config::WITNESS_COLS, advice.len());
+        assert!(fixed.len() >= RMD160Config::FIXED_COLS,
+            "rmd160 needs {} fixed columns, got {}", RMD160Config::FIXED_COLS, fixed.len());
+        let witness: [Column<Advice>; RMD160Config::WITNESS_COLS] =
+                advice[..RMD160Config::WITNESS_COLS].try_into().unwrap();
+        let fixed: [Column<Fixed>; RMD160Config::FIXED_COLS] =
+                fixed[..RMD160Config::FIXED_COLS].try_into().unwrap();
+        // complex selectors since they also gate the byte lookups
+        let selector= [0; RMD160Config::SELECTORS]
+                .map(|_|cs.complex_selector());
+        witness.map(|x| cs.enable_equality(x));
+        cs.enable_constant(fixed[0]);
+        let instance = options.enable_public_digest.then(|| {
+            let instance = cs.instance_column();
+            cs.enable_equality(instance);
+            instance
+        });
+
+        let config = RMD160Config {
+            fixed,
+            selector,
+            witness,
+            range: *range,
+            functions: *functions,
+            range_check: options.enable_range_check,
+            instance,
+            wide_range: options.wide_range,
+        };
+
+        if config.range_check {
+            cs.lookup("padding byte range", |meta| {
+                let step = config.get_expr(meta, PadGate::step());
+                let b_next = config.get_expr(meta, PadGate::b_next());
+                vec![(step * b_next, range.col)]
+            });
+
+            for i in 0..4 {
+                cs.lookup("r limb range", |meta| {
+                    let hsel = config.get_expr(meta, RoundGate::hsel(0));
+                    let limb = config.get_expr(meta, RoundGate::rlimb(i));
+                    vec![(hsel * limb, range.col)]
+                });
+            }
+
+            // the limbs of b, c and d feed the boolean functions of the round,
+            // limbs sum only checks that they recombine
+            for (name, limb) in [
+                ("b limb range", RoundGate::blimb as fn(usize) -> GateCell),
+                ("c limb range", RoundGate::climb),
+                ("d limb range", RoundGate::dlimb),
+            ] {
+                match config.wide_range {
+                    Some(wide) => for i in 0..2 {
+                        wide.lookup_range(cs, name, |meta| {
+                            let hsel = config.get_expr(meta, RoundGate::hsel(0));
+                            let low = config.get_expr(meta, limb(2 * i));
+                            let high = config.get_expr(meta, limb(2 * i + 1));
+                            hsel * (low + high * F::from(1 << 8))
+                        });
+                    },
+                    None => for i in 0..4 {
+                        cs.lookup(name, |meta| {
+                            let hsel = config.get_expr(meta, RoundGate::hsel(0));
+                            let limb = config.get_expr(meta, limb(i));
+                            vec![(hsel * limb, range.col)]
+                        });
+                    },
+                }
+            }
+
+            for limb in [RoundGate::w1_l_limb, RoundGate::w1_l_shl_limb, RoundGate::w1_h_limb, RoundGate::w1_h_shl_limb] {
+                for i in 0..4 {
+                    cs.lookup("w1 split limb range", |meta| {
+                        let hsel = config.get_expr(meta, RoundGate::hsel(0));
+                        let limb = config.get_expr(meta, limb(i));
+                        vec![(hsel * limb, range.col)]
+                    });
+                }
+            }
+
+            for limb in [RoundGate::w4_l_shl_limb, RoundGate::w4_h_limb, RoundGate::w4_h_shl_limb] {
+                for i in 0..4 {
+                    cs.lookup("w4 split limb range", |meta| {
+                        let hsel = config.get_expr(meta, RoundGate::hsel(0));
+                        let limb = config.get_expr(meta, limb(i));
+                        vec![(hsel * limb, range.col)]
+                    });
+                }
+            }
+
+            // a disabled row looks up (0, 0, 0, 0, 0) which is f(0, 0, 0) = 0
+            for i in 0..8 {
+                cs.lookup("round function", |meta| {
+                    let hsel = config.get_expr(meta, RoundGate::hsel(0));
+                    let func = config.get_expr(meta, RoundGate::func());
+                    let [bn, cn, dn, rn] = [RoundGate::bnibble, RoundGate::cnibble, RoundGate::dnibble, RoundGate::rnibble]
+                        .map(|nibble| config.get_expr(meta, nibble(i)));
+                    [func, bn, cn, dn, rn].into_iter()
+                        .zip(functions.function)
+                        .map(|(expr, col)| (hsel.clone() * expr, col))
+                        .collect()
+                });
+            }
+
+            for i in 0..8 {
+                cs.lookup("length limb range", |meta| {
+                    let lsel = config.get_expr(meta, LengthGate::lsel());
+                    let limb = config.get_expr(meta, LengthGate::limb(i));
+                    vec![(lsel * limb, range.col)]
+                });
+            }
+
+            for i in 0..4 {
+                cs.lookup("u32 word limb range", |meta| {
+                    let usel = config.get_expr(meta, WordGate::word());
+                    let limb = config.get_expr(meta, WordGate::limb(i));
+                    vec![(usel * limb, range.col)]
+                });
+            }
+        }
+
+        cs.create_gate("sum with bound", |meta| {
+            let mut sum_r = config.get_expr(meta, RoundGate::rlimb(0));
+            for i in 1..4 {
+                let limb = config.get_expr(meta, RoundGate::rlimb(i));
+                sum_r = sum_r + limb * F::from(1u64 << (8*i));
+            }
+            let w0 = config.get_expr(meta, RoundGate::w0());
+            let wb = config.get_expr(meta, RoundGate::wb());
+            let wc = config.get_expr(meta, RoundGate::wc());
+            let a = config.get_expr(meta, RoundGate::a());
+            let x = config.get_expr(meta, RoundGate::x());
+            let offset = config.get_expr(meta, RoundGate::offset());
+            let hsel = config.get_expr(meta, RoundGate::hsel(0));
+            // wc is the carry of a sum of four u32 words thus within 0..=3
+            let wc_range = (0..4).fold(hsel.clone(), |acc, i| {
+                acc * (wc.clone() - constant!(F::from(i as u64)))
+            });
+            vec![
+                (wb.clone() - sum_r - a - x - offset) * hsel.clone(),
+                wc_range,
                 (w0 + wc * F::from(1u64 << 32) - wb) * hsel,
             ] 
         });
@@ -349,9 +1569,260 @@ impl<F: FieldExt> RMD160Chip<F> {
             ]
         });
 
+        cs.create_gate("w1 split", |meta| {
+            let hsel = config.get_expr(meta, RoundGate::hsel(0));
+            let w1l = config.get_expr(meta, RoundGate::w1_l());
+            let w1h = config.get_expr(meta, RoundGate::w1_h());
+            let shift = config.get_expr(meta, RoundGate::w1_r());
+            let shift2 = config.get_expr(meta, RoundGate::w1_rr());
+            let mut sums = vec![];
+            for limb in [RoundGate::w1_l_limb, RoundGate::w1_l_shl_limb, RoundGate::w1_h_limb, RoundGate::w1_h_shl_limb] {
+                let mut sum = config.get_expr(meta, limb(0));
+                for i in 1..4 {
+                    sum = sum + config.get_expr(meta, limb(i)) * F::from(1u64 << (8*i));
+                }
+                sums.push(sum);
+            }
+            let [l, ls, h, hs]: [Expression<F>; 4] = sums.try_into().unwrap();
+            vec![
+                (l - w1l.clone()) * hsel.clone(),
+                (ls - w1l * shift) * hsel.clone(),
+                (h - w1h.clone()) * hsel.clone(),
+                (hs - w1h * shift2) * hsel,
+            ]
+        });
+
+        cs.create_gate("w4 split", |meta| {
+            let hsel = config.get_expr(meta, RoundGate::hsel(0));
+            let w4l = config.get_expr(meta, RoundGate::w4_l());
+            let w4h = config.get_expr(meta, RoundGate::w4_h());
+            let mut sums = vec![];
+            for limb in [RoundGate::w4_l_shl_limb, RoundGate::w4_h_limb, RoundGate::w4_h_shl_limb] {
+                let mut sum = config.get_expr(meta, limb(0));
+                for i in 1..4 {
+                    sum = sum + config.get_expr(meta, limb(i)) * F::from(1u64 << (8*i));
+                }
+                sums.push(sum);
+            }
+            let [ms, k, ks]: [Expression<F>; 3] = sums.try_into().unwrap();
+            vec![
+                (ms - w4l * F::from(1u64 << 10)) * hsel.clone(),
+                (k - w4h.clone()) * hsel.clone(),
+                (ks - w4h * F::from(1u64 << 22)) * hsel,
+            ]
+        });
+
+        cs.create_gate("nibbles sum", |meta| {
+            let hsel = config.get_expr(meta, RoundGate::hsel(0));
+            let mut constraints = vec![];
+            for (limb, nibble) in [
+                (RoundGate::blimb as fn(usize) -> GateCell, RoundGate::bnibble as fn(usize) -> GateCell),
+                (RoundGate::climb, RoundGate::cnibble),
+                (RoundGate::dlimb, RoundGate::dnibble),
+                (RoundGate::rlimb, RoundGate::rnibble),
+            ] {
+                for i in 0..4 {
+                    let limb = config.get_expr(meta, limb(i));
+                    let low = config.get_expr(meta, nibble(2*i));
+                    let high = config.get_expr(meta, nibble(2*i + 1));
+                    constraints.push((low + high * F::from(16) - limb) * hsel.clone());
+                }
+            }
+            constraints
+        });
+
+        cs.create_gate("compress", |meta| {
+            let rsel = config.get_expr(meta, CompressGate::rsel(0));
+            let mut constraints = vec![];
+            for i in 0..5 {
+                let [r0, r1, r2, sum, ca, new] = CompressGate::lane(i)
+                    .map(|cell| config.get_expr(meta, cell));
+                // the carry of a sum of three u32 words is within 0..=2
+                let ca_range = (0..3).fold(rsel.clone(), |acc, k| {
+                    acc * (ca.clone() - constant!(F::from(k as u64)))
+                });
+                constraints.push((sum.clone() - r0 - r1 - r2) * rsel.clone());
+                constraints.push((new + ca * F::from(1u64 << 32) - sum) * rsel.clone());
+                constraints.push(ca_range);
+            }
+            constraints
+        });
+
+        cs.create_gate("digest pack", |meta| {
+            let psel = config.get_expr(meta, PackGate::psel());
+            let low = config.get_expr(meta, PackGate::low());
+            let mut sum = config.get_expr(meta, PackGate::w(0));
+            for i in 1..4 {
+                let w = config.get_expr(meta, PackGate::w(i));
+                sum = sum + w * F::from_u128(1u128 << (32*i));
+            }
+            vec![(sum - low) * psel]
+        });
+
+        cs.create_gate("length field", |meta| {
+            let lsel = config.get_expr(meta, LengthGate::lsel());
+            let len = config.get_expr(meta, LengthGate::len());
+            let lo = config.get_expr(meta, LengthGate::lo());
+            let hi = config.get_expr(meta, LengthGate::hi());
+            let mut sum_lo = config.get_expr(meta, LengthGate::limb(0));
+            let mut sum_hi = config.get_expr(meta, LengthGate::limb(4));
+            for i in 1..4 {
+                sum_lo = sum_lo + config.get_expr(meta, LengthGate::limb(i)) * F::from(1u64 << (8*i));
+                sum_hi = sum_hi + config.get_expr(meta, LengthGate::limb(i + 4)) * F::from(1u64 << (8*i));
+            }
+            vec![
+                (sum_lo - lo.clone()) * lsel.clone(),
+                (sum_hi - hi.clone()) * lsel.clone(),
+                (lo + hi * F::from(1u64 << 32) - len * constant!(F::from(8u64))) * lsel,
+            ]
+        });
+
+        cs.create_gate("padding head", |meta| {
+            let head = config.get_expr(meta, PadGate::head());
+            let m = config.get_expr(meta, PadGate::m());
+            let cnt = config.get_expr(meta, PadGate::cnt());
+            vec![
+                (m - constant!(F::one())) * head.clone(),
+                cnt * head,
+            ]
+        });
+
+        cs.create_gate("padding step", |meta| {
+            let step = config.get_expr(meta, PadGate::step());
+            let m = config.get_expr(meta, PadGate::m());
+            let m_next = config.get_expr(meta, PadGate::m_next());
+            let cnt = config.get_expr(meta, PadGate::cnt());
+            let cnt_next = config.get_expr(meta, PadGate::cnt_next());
+            vec![
+                m_next.clone() * (constant!(F::one()) - m_next.clone()) * step.clone(),
+                m_next.clone() * (constant!(F::one()) - m) * step.clone(),
+                (cnt_next - cnt - m_next) * step,
+            ]
+        });
+
+        cs.create_gate("padding marker", |meta| {
+            let body = config.get_expr(meta, PadGate::body());
+            let m = config.get_expr(meta, PadGate::m());
+            let m_next = config.get_expr(meta, PadGate::m_next());
+            let b_next = config.get_expr(meta, PadGate::b_next());
+            vec![
+                ((constant!(F::one()) - m_next.clone()) * b_next
+                    - (m - m_next) * constant!(F::from(0x80u64))) * body,
+            ]
+        });
+
+        cs.create_gate("padding word", |meta| {
+            let word = config.get_expr(meta, PadGate::word());
+            let w = config.get_expr(meta, PadGate::w());
+            let mut sum = config.get_expr(meta, PadGate::byte(0));
+            for i in 1..4 {
+                sum = sum + config.get_expr(meta, PadGate::byte(i)) * F::from(1u64 << (8*i));
+            }
+            vec![(sum - w) * word]
+        });
+
+        cs.create_gate("padding bound", |meta| {
+            let bound = config.get_expr(meta, PadGate::bound());
+            let m = config.get_expr(meta, PadGate::m());
+            let flag = config.get_expr(meta, PadGate::flag());
+            vec![(m - flag) * bound]
+        });
+
+        cs.create_gate("pair constants", |meta| {
+            let pair = config.get_expr(meta, PairGate::pair());
+            let mut constraints = vec![];
+            for i in 0..6 {
+                let w = config.get_expr(meta, PairGate::tail(i));
+                constraints.push((w - constant!(F::from(PAIR_TAIL[i] as u64))) * pair.clone());
+            }
+            for i in 0..5 {
+                let h = config.get_expr(meta, PairGate::iv(i));
+                constraints.push((h - constant!(F::from(H0[i] as u64))) * pair.clone());
+            }
+            constraints
+        });
+
+        cs.create_gate("conditional swap", |meta| {
+            let swap = config.get_expr(meta, SwapGate::swap());
+            let s = config.get_expr(meta, SwapGate::s());
+            let a = config.get_expr(meta, SwapGate::a());
+            let b = config.get_expr(meta, SwapGate::b());
+            let l = config.get_expr(meta, SwapGate::l());
+            let r = config.get_expr(meta, SwapGate::r());
+            vec![
+                s.clone() * (constant!(F::one()) - s.clone()) * swap.clone(),
+                (l - a.clone() - s.clone() * (b.clone() - a.clone())) * swap.clone(),
+                (r - b.clone() - s * (a - b)) * swap,
+            ]
+        });
+
+        cs.create_gate("hmac key", |meta| {
+            let key = config.get_expr(meta, HmacGate::key());
+            let k = config.get_expr(meta, HmacGate::k());
+            let ipad = config.get_expr(meta, HmacGate::ipad());
+            let opad = config.get_expr(meta, HmacGate::opad());
+            let bits = (0..8).map(|i| config.get_expr(meta, HmacGate::bit(i))).collect::<Vec<_>>();
+            // the bit i of k ^ c is 1 - ki where c has its bit i set
+            let xor = |c: u8| bits.iter().enumerate().fold(constant!(F::zero()), |acc, (i, bit)| {
+                let flipped = if (c >> i) & 1 == 1 { constant!(F::one()) - bit.clone() } else { bit.clone() };
+                acc + flipped * F::from(1u64 << i)
+            });
+            let mut constraints = bits.iter()
+                .map(|bit| bit.clone() * (constant!(F::one()) - bit.clone()) * key.clone())
+                .collect::<Vec<_>>();
+            constraints.push((xor(0) - k) * key.clone());
+            constraints.push((xor(HMAC_IPAD) - ipad) * key.clone());
+            constraints.push((xor(HMAC_OPAD) - opad) * key);
+            constraints
+        });
+
+        cs.create_gate("bits pack", |meta| {
+            let sel = config.get_expr(meta, BitsGate::bits());
+            let w = config.get_expr(meta, BitsGate::w());
+            let bits = (0..32).map(|i| config.get_expr(meta, BitsGate::bit(i))).collect::<Vec<_>>();
+            let sum = bits.iter().enumerate().fold(constant!(F::zero()), |acc, (i, bit)| {
+                acc + bit.clone() * F::from(1u64 << i)
+            });
+            let mut constraints = bits.iter()
+                .map(|bit| bit.clone() * (constant!(F::one()) - bit.clone()) * sel.clone())
+                .collect::<Vec<_>>();
+            constraints.push((sum - w) * sel);
+            constraints
+        });
+
+        cs.create_gate("u32 word", |meta| {
+            let usel = config.get_expr(meta, WordGate::word());
+            let w = config.get_expr(meta, WordGate::w());
+            let sum = (0..4).fold(constant!(F::zero()), |acc, i| {
+                acc + config.get_expr(meta, WordGate::limb(i)) * F::from(1u64 << (8 * i))
+            });
+            vec![(sum - w) * usel]
+        });
+
         config
     }
 
+    /// bound the words to u32 on one row each from start_offset: every
+    /// word is copied next to its four bytes, which are looked up in the
+    /// byte range table (see the u32 word layout)
+    fn assign_u32_words(
+        &self,
+        region: &mut Region<F>,
+        start_offset: usize,
+        words: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        for (i, word) in words.iter().enumerate() {
+            let offset = start_offset + i;
+            self.bind_cell(region, offset, WordGate::w(), word)?;
+            let bytes = cell_to_u32(word).to_le_bytes();
+            for (j, byte) in bytes.iter().enumerate() {
+                self.assign_cell(region, offset, WordGate::limb(j), F::from(*byte as u64))?;
+            }
+            self.enable_selector(region, offset, WordGate::word(), F::one())?;
+        }
+        Ok(())
+    }
+
     fn assign_cell(
         &self,
         region: &mut Region<F>,
@@ -360,7 +1831,12 @@ impl<F: FieldExt> RMD160Chip<F> {
         value: F,
     ) -> Result<AssignedCell<F, F>, Error> {
         let cell = gate_cell.cell;
-        //println!("Assign Cell at {} {} {:?}", start_offset, gate_cell.name, value);
+        debug_assert!(cell[0] != 0 || cell[1] < WITNESS_COLS,
+            "gate cell {} assigns advice column {} but only {} are configured",
+            gate_cell.to_string(), cell[1], WITNESS_COLS);
+        debug_trace!("assign cell at {} {} {:?}", start_offset, gate_cell.name, value);
+        #[cfg(feature = "stats")]
+        { self.stats.borrow_mut().cells_assigned += 1; }
         if cell[0] == 0 { // advice
             region.assign_advice(
                 || format!("assign cell"),
@@ -388,6 +1864,12 @@ impl<F: FieldExt> RMD160Chip<F> {
         value: F,
     ) -> Result<(), Error> {
         assert!(gate_cell.cell[0] == 2);
+        #[cfg(feature = "stats")]
+        {
+            let mut stats = self.stats.borrow_mut();
+            stats.selectors_enabled += 1;
+            stats.selector_rows[gate_cell.cell[1]] += 1;
+        }
         self.config.selector[gate_cell.cell[1]].enable(region, start_offset + gate_cell.cell[2])
     }
 
@@ -402,10 +1884,13 @@ impl<F: FieldExt> RMD160Chip<F> {
         let f = cell_to_value(value);
         let cell = self.assign_cell(region, start_offset,cell, f)?;
         region.constrain_equal(cell.cell(), value.cell())?;
+        #[cfg(feature = "stats")]
+        { self.stats.borrow_mut().equality_constraints += 1; }
         Ok(cell)
     }
 
-    fn assign_next(
+    #[allow(clippy::too_many_arguments)]
+    fn assign_next<S: HashSpec>(
         &self,
         region: &mut Region<F>,
         start_offset: usize,
@@ -413,21 +1898,23 @@ impl<F: FieldExt> RMD160Chip<F> {
         input: &AssignedCell<F, F>,
         round: usize,
         index: usize,
-        shift: &[[u32; 16]; 5],
-        offset: &[u32; 5],
         pround: bool,
-    ) -> Result<[AssignedCell<F, F>; 5], Error> {
-        //println!("rol: {:?}", previous.clone().map(|x| cell_to_u32(&x)));
+        witness: &RoundWitness<F>,
+    ) -> Result<([AssignedCell<F, F>; 5], [AssignedCell<F, F>; 2], AssignedCell<F, F>), Error> {
+        let schedule = RoundSchedule::of::<S>();
+        let shift = schedule.shift(pround, round, index);
+        let offset = schedule.offset(pround, round);
         self.bind_cell(region, start_offset, RoundGate::a(), &previous[0])?;
         let b = self.bind_cell(region, start_offset, RoundGate::b(), &previous[1])?;
         self.bind_cell(region, start_offset, RoundGate::c(), &previous[2])?;
         let d = self.bind_cell(region, start_offset, RoundGate::d(), &previous[3])?;
         let e = self.bind_cell(region, start_offset, RoundGate::e(), &previous[4])?;
 
-        self.bind_cell(region, start_offset, RoundGate::x(), &input)?;
+        let x = self.bind_cell(region, start_offset, RoundGate::x(), &input)?;
 
-        self.assign_cell(region, start_offset, RoundGate::w1_r(), F::from(1u64 << shift[round][index]))?;
-        self.assign_cell(region, start_offset, RoundGate::w1_rr(), F::from(1u64 << (32 - shift[round][index])))?;
+        let (w1_r, w1_rr) = rotate_multipliers(shift);
+        self.assign_cell(region, start_offset, RoundGate::w1_r(), F::from(w1_r))?;
+        self.assign_cell(region, start_offset, RoundGate::w1_rr(), F::from(w1_rr))?;
 
         let blimbs = cell_to_limbs(&previous[1]);
         for i in 0..4 {
@@ -444,65 +1931,91 @@ impl<F: FieldExt> RMD160Chip<F> {
             self.assign_cell(region, start_offset, RoundGate::dlimb(i), dlimbs[i])?;
         }
 
-        let rol = previous.into_iter()
-            .map(|c| {
-                cell_to_u32(c)
-            })
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+        self.assign_cell(region, start_offset, RoundGate::func(), F::from(S::function(round, pround) as u64))?;
 
-        let witness = get_witnesses(round, &rol, cell_to_u32(&input), shift[round][index], offset[round], pround);
-        //self.assign_cell(region, start_offset, RoundGate::r(), F::from(witness.r as u64));
-        //
-        self.assign_cell(region, start_offset, RoundGate::offset(), F::from(offset[round] as u64))?;
+        let rol: [u32; 5] = std::array::from_fn(|i| cell_to_u32(&previous[i]));
+        debug_trace!("rol: {:?}", rol);
+
+        self.assign_cell(region, start_offset, RoundGate::offset(), F::from(offset as u64))?;
         let rlimbs = u32_to_limbs(witness.r);
 
-        let mut sum_r = rlimbs[0];
-        for i in 1..4 {
-            sum_r = sum_r + rlimbs[i] * F::from(1u64 << (8*i));
+        if self.witness_assertions {
+            if let Err(equation) = check_round_witness(witness, &rol, cell_to_u32(&input), offset) {
+                let mismatch = WitnessMismatch { round, index, pround, equation };
+                self.mismatch.set(Some(mismatch));
+                return Err(mismatch.into());
+            }
         }
-
-        assert!(sum_r == F::from(witness.r as u64));
-
-        assert!(witness.w2b == F::from(witness.w1 as u64) + F::from(cell_to_u32(&previous[4]) as u64));
-        assert!(witness.wb == F::from(witness.r as u64) + F::from(cell_to_u32(&previous[0]) as u64)
-                + F::from(cell_to_u32(&input) as u64) + F::from(offset[round] as u64));
         for i in 0..4 {
             self.assign_cell(region, start_offset, RoundGate::rlimb(i), rlimbs[i])?;
         }
+        for (nibble, value) in [
+            (RoundGate::bnibble as fn(usize) -> GateCell, rol[1]),
+            (RoundGate::cnibble, rol[2]),
+            (RoundGate::dnibble, rol[3]),
+            (RoundGate::rnibble, witness.r),
+        ] {
+            for i in 0..8 {
+                self.assign_cell(region, start_offset, nibble(i), F::from(((value >> (4*i)) & 0xf) as u64))?;
+            }
+        }
         self.assign_cell(region, start_offset, RoundGate::w0(), F::from(witness.w0 as u64))?;
         self.assign_cell(region, start_offset, RoundGate::wb(), witness.wb)?;
-        self.assign_cell(region, start_offset, RoundGate::wc(), F::from(witness.wc as u64))?;
+        let wc = self.assign_cell(region, start_offset, RoundGate::wc(), F::from(witness.wc.get()))?;
         self.assign_cell(region, start_offset, RoundGate::w1(), F::from(witness.w1 as u64))?;
         self.assign_cell(region, start_offset, RoundGate::w1_h(), F::from(witness.w1_h as u64))?;
         self.assign_cell(region, start_offset, RoundGate::w1_l(), F::from(witness.w1_l as u64))?;
+        let split = [
+            (RoundGate::w1_l_limb as fn(usize) -> GateCell, witness.w1_l),
+            (RoundGate::w1_l_shl_limb, (witness.w1_l as u64 * w1_r) as u32),
+            (RoundGate::w1_h_limb, witness.w1_h),
+            (RoundGate::w1_h_shl_limb, (witness.w1_h as u64 * w1_rr) as u32),
+            (RoundGate::w4_l_shl_limb, witness.w4_l << 10),
+            (RoundGate::w4_h_limb, witness.w4_h),
+            (RoundGate::w4_h_shl_limb, witness.w4_h << 22),
+        ];
+        for (limb, value) in split {
+            let limbs = u32_to_limbs::<F>(value);
+            for i in 0..4 {
+                self.assign_cell(region, start_offset, limb(i), limbs[i])?;
+            }
+        }
         self.assign_cell(region, start_offset, RoundGate::w4_h(), F::from(witness.w4_h as u64))?;
         self.assign_cell(region, start_offset, RoundGate::w4_l(),F::from(witness.w4_l as u64))?;
         self.assign_cell(region, start_offset, RoundGate::w2b(),witness.w2b)?;
-        self.assign_cell(region, start_offset, RoundGate::w2c(),F::from(witness.w2c as u64))?;
+        let w2c = self.assign_cell(region, start_offset, RoundGate::w2c(),F::from(witness.w2c.get()))?;
         self.enable_selector(region, start_offset, RoundGate::hsel(0), F::one())?;
         let a = self.assign_cell(region, start_offset, RoundGate::a_next(), F::from(witness.a_next as u64))?;
         let c = self.assign_cell(region, start_offset, RoundGate::c_next(), F::from(witness.c_next as u64))?;
-        Ok([e, a, b, c, d])
+        Ok(([e, a, b, c, d], [wc, w2c], x))
     }
 
-    fn rotate_inputs(
+    /// combine the chaining value r0 of a block with the outputs r1 and r2
+    /// of its two lines. The r0 cells are copy constrained into the a .. e
+    /// lanes of the compress gate (likewise r1 and r2), so the digest is
+    /// bound to the actual start state and not to the witness values alone.
+    pub fn assign_compress(
         &self,
-        inputs: &[AssignedCell<F, F>; 16],
-        round_shift: [usize; 16],
-    ) -> [AssignedCell<F, F>; 16] {
-        round_shift.map(|i| inputs[i].clone())
+        region: &mut Region<F>,
+        start_offset: usize,
+        r0: &[AssignedCell<F, F>; 5],
+        r1: &[AssignedCell<F, F>; 5],
+        r2: &[AssignedCell<F, F>; 5],
+    ) -> Result<[AssignedCell<F, F>; 5], Error> {
+        let (digest, _) = self.assign_compress_with_carries(region, start_offset, r0, r1, r2)?;
+        Ok(digest)
     }
 
-    pub fn assign_compress(
+    /// same as assign_compress but also returns the carry cells of the
+    /// five lanes, in the order of the digest words
+    pub fn assign_compress_with_carries(
         &self,
         region: &mut Region<F>,
         start_offset: usize,
         r0: &[AssignedCell<F, F>; 5],
         r1: &[AssignedCell<F, F>; 5],
         r2: &[AssignedCell<F, F>; 5],
-    ) -> Result<[AssignedCell<F, F>; 5], Error> {
+    ) -> Result<([AssignedCell<F, F>; 5], [AssignedCell<F, F>; 5]), Error> {
         self.bind_cell(region, start_offset, CompressGate::a(), &r0[0])?;
         self.bind_cell(region, start_offset, CompressGate::b(), &r0[1])?;
         self.bind_cell(region, start_offset, CompressGate::c(), &r0[2])?;
@@ -515,268 +2028,4364 @@ impl<F: FieldExt> RMD160Chip<F> {
         self.bind_cell(region, start_offset, CompressGate::e1(), &r1[4])?;
         self.bind_cell(region, start_offset, CompressGate::a2(), &r2[0])?;
         self.bind_cell(region, start_offset, CompressGate::b2(), &r2[1])?;
+        self.enable_selector(region, start_offset, CompressGate::rsel(0), F::one())?;
         self.bind_cell(region, start_offset, CompressGate::c2(), &r2[2])?;
         self.bind_cell(region, start_offset, CompressGate::d2(), &r2[3])?;
         self.bind_cell(region, start_offset, CompressGate::e2(), &r2[4])?;
 
-        let anew = {
-            let anew = cell_to_u32(&r0[0])
-                .wrapping_add(cell_to_u32(&r1[1]))
-                .wrapping_add(cell_to_u32(&r2[2]));
+        let (anew, ca0) = {
+            let (anew, ca0) = carry_split(cell_to_u32(&r0[0]) as u64
+                + cell_to_u32(&r1[1]) as u64
+                + cell_to_u32(&r2[2]) as u64);
             let sum0 = cell_to_value(&r0[0])
                 + cell_to_value(&r1[1])
                 + cell_to_value(&r2[2]);
-            let ca0 = (field_to_u64(&sum0) - anew as u64) >> 32;
             self.assign_cell(region, start_offset, CompressGate::sum0(), sum0)?;
-            self.assign_cell(region, start_offset, CompressGate::ca0(), F::from(ca0))?;
-            self.assign_cell(region, start_offset, CompressGate::anew(), F::from(anew as u64))?
+            let ca0 = self.assign_cell(region, start_offset, CompressGate::ca0(), F::from(ca0))?;
+            (self.assign_cell(region, start_offset, CompressGate::anew(), F::from(anew as u64))?, ca0)
         };
 
-        let bnew = {
-            let bnew = cell_to_u32(&r0[1])
-                .wrapping_add(cell_to_u32(&r1[2]))
-                .wrapping_add(cell_to_u32(&r2[3]));
+        let (bnew, ca1) = {
+            let (bnew, ca1) = carry_split(cell_to_u32(&r0[1]) as u64
+                + cell_to_u32(&r1[2]) as u64
+                + cell_to_u32(&r2[3]) as u64);
             let sum1 = cell_to_value(&r0[1])
                 + cell_to_value(&r1[2])
                 + cell_to_value(&r2[3]);
-            let ca1 = (field_to_u64(&sum1) - bnew as u64) >> 32;
             self.assign_cell(region, start_offset, CompressGate::sum1(), sum1)?;
-            self.assign_cell(region, start_offset, CompressGate::ca1(), F::from(ca1))?;
-            self.assign_cell(region, start_offset, CompressGate::bnew(), F::from(bnew as u64))?
+            let ca1 = self.assign_cell(region, start_offset, CompressGate::ca1(), F::from(ca1))?;
+            (self.assign_cell(region, start_offset, CompressGate::bnew(), F::from(bnew as u64))?, ca1)
         };
 
-        let cnew = {
-            let cnew = cell_to_u32(&r0[2])
-                .wrapping_add(cell_to_u32(&r1[3]))
-                .wrapping_add(cell_to_u32(&r2[4]));
+        let (cnew, ca2) = {
+            let (cnew, ca2) = carry_split(cell_to_u32(&r0[2]) as u64
+                + cell_to_u32(&r1[3]) as u64
+                + cell_to_u32(&r2[4]) as u64);
             let sum2 = cell_to_value(&r0[2])
                 + cell_to_value(&r1[3])
                 + cell_to_value(&r2[4]);
-            let ca2 = (field_to_u64(&sum2) - cnew as u64) >> 32;
             self.assign_cell(region, start_offset, CompressGate::sum2(), sum2)?;
-            self.assign_cell(region, start_offset, CompressGate::ca0(), F::from(ca2))?;
-            self.assign_cell(region, start_offset, CompressGate::cnew(), F::from(cnew as u64))?
+            let ca2 = self.assign_cell(region, start_offset, CompressGate::ca2(), F::from(ca2))?;
+            (self.assign_cell(region, start_offset, CompressGate::cnew(), F::from(cnew as u64))?, ca2)
         };
 
-        let dnew = {
-            let dnew = cell_to_u32(&r0[3])
-                .wrapping_add(cell_to_u32(&r1[4]))
-                .wrapping_add(cell_to_u32(&r2[0]));
+        let (dnew, ca3) = {
+            let (dnew, ca3) = carry_split(cell_to_u32(&r0[3]) as u64
+                + cell_to_u32(&r1[4]) as u64
+                + cell_to_u32(&r2[0]) as u64);
             let sum3 = cell_to_value(&r0[3])
                 + cell_to_value(&r1[4])
                 + cell_to_value(&r2[0]);
-            let ca3 = (field_to_u64(&sum3) - dnew as u64) >> 32;
             self.assign_cell(region, start_offset, CompressGate::sum3(), sum3)?;
-            self.assign_cell(region, start_offset, CompressGate::ca3(), F::from(ca3))?;
-            self.assign_cell(region, start_offset, CompressGate::dnew(), F::from(dnew as u64))?
+            let ca3 = self.assign_cell(region, start_offset, CompressGate::ca3(), F::from(ca3))?;
+            (self.assign_cell(region, start_offset, CompressGate::dnew(), F::from(dnew as u64))?, ca3)
         };
 
-        let enew = {
-            let enew = cell_to_u32(&r0[4])
-                .wrapping_add(cell_to_u32(&r1[0]))
-                .wrapping_add(cell_to_u32(&r2[1]));
+        let (enew, ca4) = {
+            let (enew, ca4) = carry_split(cell_to_u32(&r0[4]) as u64
+                + cell_to_u32(&r1[0]) as u64
+                + cell_to_u32(&r2[1]) as u64);
             let sum4 = cell_to_value(&r0[4])
                 + cell_to_value(&r1[0])
                 + cell_to_value(&r2[1]);
-            let ca4 = (field_to_u64(&sum4) - enew as u64) >> 32;
             self.assign_cell(region, start_offset, CompressGate::sum4(), sum4)?;
-            self.assign_cell(region, start_offset, CompressGate::ca4(), F::from(ca4))?;
-            self.assign_cell(region, start_offset, CompressGate::enew(), F::from(enew as u64))?
+            let ca4 = self.assign_cell(region, start_offset, CompressGate::ca4(), F::from(ca4))?;
+            (self.assign_cell(region, start_offset, CompressGate::enew(), F::from(enew as u64))?, ca4)
         };
 
-        Ok([anew, bnew, cnew, dnew, enew])
+        // the lane computed from a + b1 + c2 is the last word of the digest
+        let digest = [bnew, cnew, dnew, enew, anew];
+        self.assign_u32_words(region, start_offset + 5, &digest)?;
+        Ok((digest, [ca1, ca2, ca3, ca4, ca0]))
     }
 
-
-    pub fn assign_content(
+    /// combine the lines of one block by the rule of the spec S and return
+    /// the digest with its carries, only the cross combination of a five
+    /// word state has a gate so far
+    pub fn assign_combine<S: HashSpec>(
         &self,
-        layouter: &mut impl Layouter<F>,
-        start_buf: &[AssignedCell<F, F>; 5],
-        inputs: &[AssignedCell<F, F>; 16],
-    ) -> Result<[AssignedCell<F, F>; 5], Error> {
-        let r = layouter.assign_region(
-            || "leaf layer",
-            |mut region| {
-                let mut r1 = start_buf.clone();
-                let mut start_offset = 0;
-                for round in 0..5 {
-                    for index in 0..16 {
-                        r1 = self.assign_next(
-                            &mut region,
-                            start_offset,
-                            &r1,
-                            &self.rotate_inputs(inputs, O[round])[index],
-                            round,
-                            index,
-                            &R,
-                            &ROUNDS_OFFSET,
-                            false,
-                        )?;
-                        start_offset += 5;
-                    }
-                }
-            println!("{} {} {} {} {}",
-                cell_to_u32(&r1[0]),
-                cell_to_u32(&r1[1]),
-                cell_to_u32(&r1[2]),
-                cell_to_u32(&r1[3]),
-                cell_to_u32(&r1[4]),
-            );
-
-
-                let mut r2 = start_buf.clone();
-                for round in 0..5 {
-                    for index in 0..16 {
-                        r2 = self.assign_next(
-                            &mut region,
-                            start_offset,
-                            &r2,
-                            &self.rotate_inputs(&inputs, PO[round])[index],
-                            round,
-                            index,
-                            &PR,
-                            &PROUNDS_OFFSET,
-                            true
-                        )?;
-                        start_offset += 5;
-                    }
-                }
-                self.assign_compress(&mut region, start_offset, start_buf, &r1, &r2)
-            }
-        )?;
-        Ok(r)
+        region: &mut Region<F>,
+        start_offset: usize,
+        r0: &[AssignedCell<F, F>; 5],
+        r1: &[AssignedCell<F, F>; 5],
+        r2: &[AssignedCell<F, F>; 5],
+    ) -> Result<([AssignedCell<F, F>; 5], [AssignedCell<F, F>; 5]), Error> {
+        match S::COMBINATION {
+            Combination::Cross if S::STATE_WORDS == 5 => self.assign_compress_with_carries(region, start_offset, r0, r1, r2),
+            _ => Err(Error::Synthesis),
+        }
     }
-}
-
-
-
-#[cfg(test)]
-mod tests {
-    use halo2_proofs::halo2curves::bn256::Fr;
-    use halo2_proofs::dev::MockProver;
 
-    use crate::utils::cell_to_u32;
-    use halo2_proofs::{
-        circuit::{Value, Chip, Layouter, AssignedCell, SimpleFloorPlanner},
-        plonk::{
-            Advice, Circuit, Column, ConstraintSystem, Error,
-        },
-    };
-
-    use super::RMD160Chip;
-    use super::RMD160Config;
-    use crate::host::rmd160::H0;
+    /// rows of the round and compress regions of num_blocks blocks, per
+    /// block 80 rounds of ROWS_PER_ROUND rows on each of the two lines and
+    /// ROWS_PER_COMPRESS rows for the compress step.
+    /// The padding, the inputs and the lookup tables come on top: whatever
+    /// the rows, the round function table sets k >= RoundFunctionTable::MIN_K.
+    pub fn estimated_rows(num_blocks: usize) -> usize {
+        num_blocks * (160 * ROWS_PER_ROUND + ROWS_PER_COMPRESS)
+    }
 
-    #[derive(Clone, Debug)]
-    pub struct HelperChipConfig {
-        limb: Column<Advice>
+    /// the columns the chip configures: 7 advice columns with equality,
+    /// 1 fixed column which also holds the constants, 14 complex selectors
+    /// and the 6 lookup columns of the RangeTable and the RoundFunctionTable
+    /// (plus the instance column if the public digest is enabled). A circuit
+    /// of n blocks needs a k with 2^k above both estimated_rows(n) and
+    /// table_rows plus the blinding rows, thus at least min_k.
+    pub fn layout_info() -> LayoutInfo {
+        LayoutInfo {
+            advice_columns: RMD160Config::WITNESS_COLS,
+            fixed_columns: RMD160Config::FIXED_COLS,
+            selectors: RMD160Config::SELECTORS,
+            table_columns: RangeTable::COLUMNS + RoundFunctionTable::COLUMNS,
+            table_rows: RoundFunctionTable::ROWS,
+            min_k: RoundFunctionTable::MIN_K,
+            rows_per_round: ROWS_PER_ROUND,
+            rows_per_compress: ROWS_PER_COMPRESS,
+        }
     }
 
-    #[derive(Clone, Debug)]
-    pub struct HelperChip {
-        config: HelperChipConfig
+    /// assign one block from start_offset in the region and
+    /// return the digest together with the offset after the block.
+    /// All gates query cells at non negative rotations from the row where
+    /// their selector is enabled, so the block can start at any offset of a
+    /// region shared with other blocks or chips.
+    pub fn assign_block(
+        &self,
+        region: &mut Region<F>,
+        start_offset: usize,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([AssignedCell<F, F>; 5], usize), Error> {
+        let (digest, _, start_offset) = self.assign_block_with_lines(region, start_offset, start_buf, inputs)?;
+        Ok((digest, start_offset))
     }
 
-    impl Chip<Fr> for HelperChip {
-        type Config = HelperChipConfig;
-        type Loaded = ();
+    /// same as assign_block but also returns the outputs of the left and
+    /// the parallel line right before they are combined by the compress step
+    pub fn assign_block_with_lines(
+        &self,
+        region: &mut Region<F>,
+        start_offset: usize,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([AssignedCell<F, F>; 5], [[AssignedCell<F, F>; 5]; 2], usize), Error> {
+        let (digest, lines, _, start_offset) = self.assign_block_with_carries(region, start_offset, start_buf, inputs)?;
+        Ok((digest, lines, start_offset))
+    }
 
-        fn config(&self) -> &Self::Config {
-            &self.config
-        }
+    /// same as assign_block_with_lines but also returns the carry cells
+    /// of the rounds and of the compress step
+    pub fn assign_block_with_carries(
+        &self,
+        region: &mut Region<F>,
+        start_offset: usize,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([AssignedCell<F, F>; 5], [[AssignedCell<F, F>; 5]; 2], BlockCarries<F>, usize), Error> {
+        let ([r1, r2], rounds, _, mut start_offset) = self.assign_lines::<Rmd160Spec>(region, start_offset, start_buf, inputs)?;
+        let (digest, compress) = self.assign_combine::<Rmd160Spec>(region, start_offset, start_buf, &r1, &r2)?;
+        start_offset += ROWS_PER_COMPRESS;
+        let (wc, w2c) = rounds.into_iter().map(|[wc, w2c]| (wc, w2c)).unzip();
+        Ok((digest, [r1, r2], BlockCarries { wc, w2c, compress }, start_offset))
+    }
 
-        fn loaded(&self) -> &Self::Loaded {
-            &()
+    /// run the left and the parallel line of the spec S on one block from
+    /// start_offset and return the [left, parallel] outputs, the [wc, w2c]
+    /// carries of every round, the x cells of every step of both lines
+    /// (all copies of the inputs) and the offset after the last round
+    #[allow(clippy::type_complexity)]
+    fn assign_lines<S: HashSpec>(
+        &self,
+        region: &mut Region<F>,
+        start_offset: usize,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([[AssignedCell<F, F>; 5]; 2], Vec<[AssignedCell<F, F>; 2]>, [Vec<AssignedCell<F, F>>; 2], usize), Error> {
+        let schedule = RoundSchedule::of::<S>();
+        let iv = std::array::from_fn(|i| cell_to_u32(&start_buf[i]));
+        let words = std::array::from_fn(|i| cell_to_u32(&inputs[i]));
+        let mut witnesses = block_witnesses::<F, S>(&iv, &words).into_iter();
+        let mut start_offset = start_offset;
+        let mut lines = vec![];
+        let mut carries = vec![];
+        let mut xs = [vec![], vec![]];
+        for pround in [false, true] {
+            // the start state is only borrowed, each round hands its output on
+            let mut r: Option<[AssignedCell<F, F>; 5]> = None;
+            for round in 0..schedule.rounds() {
+                for (index, idx) in schedule.order(pround, round).iter().enumerate() {
+                    let (next, carry, x) = self.assign_next::<S>(
+                        region,
+                        start_offset,
+                        r.as_ref().unwrap_or(start_buf),
+                        &inputs[*idx],
+                        round,
+                        index,
+                        pround,
+                        &witnesses.next().unwrap(),
+                    )?;
+                    r = Some(next);
+                    carries.push(carry);
+                    xs[pround as usize].push(x);
+                    start_offset += ROWS_PER_ROUND;
+                }
+            }
+            lines.push(r.unwrap_or_else(|| start_buf.clone()));
         }
+        Ok((lines.try_into().unwrap(), carries, xs, start_offset))
     }
 
-    impl HelperChip {
+    /// copy the 16 words of a block into one message region, the x cells
+    /// of both lines are then copy constrained to these cells, so the left
+    /// and the parallel line provably read the same words
+    pub fn assign_message_words(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<[AssignedCell<F, F>; 16], Error> {
+        layouter.assign_region(
+            || "message words",
+            |mut region| {
+                let mut words = vec![];
+                for (i, input) in inputs.iter().enumerate() {
+                    words.push(self.bind_cell(&mut region, i, GateCell::adv(0, 0, "message word"), input)?);
+                }
+                Ok(words.try_into().unwrap())
+            }
+        )
+    }
+
+    /// hash one block and return the digest with the [left, parallel] line outputs
+    pub fn assign_content_with_lines(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([AssignedCell<F, F>; 5], [[AssignedCell<F, F>; 5]; 2]), RMD160Error> {
+        Self::check_state(start_buf, inputs)?;
+        let inputs = &self.assign_message_words(layouter, inputs)?;
+        layouter.assign_region(
+            || "leaf layer",
+            |mut region| {
+                let (digest, lines, _) = self.assign_block_with_lines(&mut region, 0, start_buf, inputs)?;
+                Ok((digest, lines))
+            }
+        ).map_err(|e| self.block_error(e))
+    }
+
+    /// hash one block and return (r1, r2, digest) where r1 and r2 are the
+    /// outputs of the left and the parallel line, so tests can tell a wrong
+    /// round phase from a wrong compress step
+    pub fn assign_content_debug(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([AssignedCell<F, F>; 5], [AssignedCell<F, F>; 5], [AssignedCell<F, F>; 5]), RMD160Error> {
+        let (digest, [r1, r2]) = self.assign_content_with_lines(layouter, start_buf, inputs)?;
+        Ok((r1, r2, digest))
+    }
+
+    /// hash one block and return the digest with the carry witnesses of
+    /// the rounds and of the compress step, so they can be inspected or
+    /// copy constrained by an enclosing circuit
+    pub fn assign_content_with_carries(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<([AssignedCell<F, F>; 5], BlockCarries<F>), RMD160Error> {
+        Self::check_state(start_buf, inputs)?;
+        let inputs = &self.assign_message_words(layouter, inputs)?;
+        layouter.assign_region(
+            || "leaf layer",
+            |mut region| {
+                let (digest, _, carries, _) = self.assign_block_with_carries(&mut region, 0, start_buf, inputs)?;
+                Ok((digest, carries))
+            }
+        ).map_err(|e| self.block_error(e))
+    }
+
+    /// hash one block from start_buf and return the digest, the returned
+    /// cells are the output cells of the compress step
+    pub fn assign_content(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        Self::check_state(start_buf, inputs)?;
+        let inputs = &self.assign_message_words(layouter, inputs)?;
+        let r = layouter.assign_region(
+            || "leaf layer",
+            |mut region| {
+                let (digest, _) = self.assign_block(&mut region, 0, start_buf, inputs)?;
+                Ok(digest)
+            }
+        ).map_err(|e| self.block_error(e))?;
+        Ok(r)
+    }
+
+    /// hash one block from iv inside the synthesize of an enclosing circuit.
+    /// The block gets its own region and the digest cells are returned for
+    /// further constraints, nothing is printed or exposed.
+    pub fn synthesize_sub(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        iv: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+    ) -> Result<AssignedDigest<F>, RMD160Error> {
+        Self::check_state(iv, inputs)?;
+        let inputs = &self.assign_message_words(layouter, inputs)?;
+        let digest = layouter.assign_region(
+            || "rmd160 block",
+            |mut region| {
+                let (digest, _) = self.assign_block(&mut region, 0, iv, inputs)?;
+                Ok(digest)
+            }
+        ).map_err(|e| self.block_error(e))?;
+        Ok(AssignedDigest::from_cells(digest))
+    }
+
+    /// hash one block and constrain the digest words to the instance
+    /// column at the given rows, so callers can place several public outputs
+    pub fn assign_and_expose(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+        instance: Column<Instance>,
+        digest_instance_offset: [usize; 5],
+    ) -> Result<(), RMD160Error> {
+        let digest = self.assign_content(layouter, start_buf, inputs)?;
+        for (cell, row) in digest.iter().zip(digest_instance_offset) {
+            layouter.constrain_instance(cell.cell(), instance, row)?;
+        }
+        Ok(())
+    }
+
+    /// hash one block and constrain the digest words to the constants of
+    /// expected, the proof then shows that the block hashes to expected
+    /// without handing the digest cells back to the caller
+    pub fn verify_digest(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        inputs: &[AssignedCell<F, F>; 16],
+        expected: [u32; 5],
+    ) -> Result<(), RMD160Error> {
+        let digest = self.assign_content(layouter, start_buf, inputs)?;
+        layouter.assign_region(
+            || "expected digest",
+            |mut region| {
+                for (i, (cell, w)) in digest.iter().zip(expected).enumerate() {
+                    let w = F::from(w as u64);
+                    let expected = self.assign_cell(&mut region, 0, GateCell::adv(i, 0, "expected"), w)?;
+                    region.constrain_constant(expected.cell(), w)?;
+                    region.constrain_equal(expected.cell(), cell.cell())?;
+                }
+                Ok(())
+            }
+        )?;
+        Ok(())
+    }
+
+    /// assign the 16 message words of a block and hash them from start_buf,
+    /// the words are taken as typed u32 thus no byte packing is involved
+    pub fn hash_words(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        words: &[u32; 16],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        let inputs = layouter.assign_region(
+            || "message words",
+            |mut region| {
+                let mut cells = vec![];
+                for (i, w) in words.iter().enumerate() {
+                    cells.push(self.assign_cell(&mut region, 0, GateCell::adv(0, i, "word"), F::from(*w as u64))?);
+                }
+                Ok(cells.try_into().unwrap())
+            }
+        )?;
+        self.assign_content(layouter, start_buf, &inputs)
+    }
+
+    /// pack 512 bit cells into the 16 words of a block, the bit 32 * j + i
+    /// is the bit i (counted from the least significant one) of the word j.
+    /// The bits are copied into the region, constrained to be boolean and
+    /// summed into their word by the bits pack gate.
+    pub fn assign_bits_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bits: &[AssignedCell<F, F>; 512],
+    ) -> Result<[AssignedCell<F, F>; 16], Error> {
+        layouter.assign_region(
+            || "block bits",
+            |mut region| {
+                let mut cells = vec![];
+                for (j, word_bits) in bits.chunks_exact(32).enumerate() {
+                    let row = 5 * j;
+                    let mut w = F::zero();
+                    for (i, bit) in word_bits.iter().enumerate() {
+                        let bit = self.bind_cell(&mut region, row, BitsGate::bit(i), bit)?;
+                        w += cell_to_value(&bit) * F::from(1u64 << i);
+                    }
+                    cells.push(self.assign_cell(&mut region, row, BitsGate::w(), w)?);
+                    self.enable_selector(&mut region, row, BitsGate::bits(), F::one())?;
+                }
+                Ok(cells.try_into().unwrap())
+            }
+        )
+    }
+
+    /// compress one block given as 512 bit cells from start_buf, the bits
+    /// are packed into words in circuit as assign_bits_block describes.
+    pub fn assign_content_from_bits(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        bits: &[AssignedCell<F, F>; 512],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        let inputs = self.assign_bits_block(layouter, bits)?;
+        self.assign_content(layouter, start_buf, &inputs)
+    }
+
+    /// assign the 16 words of a block given as bytes, each word is packed
+    /// from four bytes in little endian as RIPEMD-160 reads its input.
+    /// Only the words are assigned, the bytes themselves are not constrained
+    /// (see assign_padded_bytes to range check and pad the bytes in circuit).
+    pub fn assign_bytes_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &[u8; 64],
+    ) -> Result<[AssignedCell<F, F>; 16], Error> {
+        layouter.assign_region(
+            || "block bytes",
+            |mut region| {
+                let mut cells = vec![];
+                for (i, w) in block.chunks_exact(4).enumerate() {
+                    let w = u32::from_le_bytes(w.try_into().unwrap());
+                    cells.push(self.assign_cell(&mut region, 0, GateCell::adv(0, i, "word"), F::from(w as u64))?);
+                }
+                Ok(cells.try_into().unwrap())
+            }
+        )
+    }
+
+    /// assign the bytes of padded blocks and constrain the padding: the
+    /// first len bytes are free message bytes, then comes the 0x80 marker
+    /// followed by zeros up to the 64 bit length field of the last block.
+    /// The number of message bytes is copy constrained to len and the
+    /// packed block words are returned for the compression.
+    /// The byte cells (col0) are looked up in the range table. Bytes which
+    /// are not a positive number of blocks are an Error::Synthesis.
+    pub fn assign_padded_bytes(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        len: &AssignedCell<F, F>,
+        bytes: &[u8],
+    ) -> Result<Vec<[AssignedCell<F, F>; 16]>, Error> {
+        let (_, blocks) = self.assign_padded_cells(layouter, len, bytes)?;
+        Ok(blocks)
+    }
+
+    /// assign_padded_bytes which also returns the byte cells
+    fn assign_padded_cells(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        len: &AssignedCell<F, F>,
+        bytes: &[u8],
+    ) -> Result<(Vec<AssignedCell<F, F>>, Vec<[AssignedCell<F, F>; 16]>), Error> {
+        if bytes.len() % 64 != 0 || bytes.is_empty() {
+            return Err(Error::Synthesis);
+        }
+        let n = bytes.len();
+        let msg_len = cell_to_u64(len).ok_or(Error::Synthesis)? as usize;
+        layouter.assign_region(
+            || "padding",
+            |mut region| {
+                self.assign_cell(&mut region, 0, PadGate::b(), F::zero())?;
+                self.assign_cell(&mut region, 0, PadGate::m(), F::one())?;
+                self.assign_cell(&mut region, 0, PadGate::cnt(), F::zero())?;
+                self.enable_selector(&mut region, 0, PadGate::head(), F::one())?;
+                let mut cnt = None;
+                let mut byte_cells = vec![];
+                for (j, b) in bytes.iter().enumerate() {
+                    let row = j + 1;
+                    let m = if j < msg_len { 1u64 } else { 0 };
+                    self.enable_selector(&mut region, j, PadGate::step(), F::one())?;
+                    if j < n - 8 {
+                        self.enable_selector(&mut region, j, PadGate::body(), F::one())?;
+                    }
+                    byte_cells.push(self.assign_cell(&mut region, row, PadGate::b(), F::from(*b as u64))?);
+                    self.assign_cell(&mut region, row, PadGate::m(), F::from(m))?;
+                    cnt = Some(self.assign_cell(&mut region, row, PadGate::cnt(), F::from(j.min(msg_len) as u64 + m))?);
+                }
+                region.constrain_equal(cnt.unwrap().cell(), len.cell())?;
+
+                // the flag before the length field is 0 and the padding is minimal
+                self.assign_cell(&mut region, n - 8, PadGate::flag(), F::zero())?;
+                self.enable_selector(&mut region, n - 8, PadGate::bound(), F::one())?;
+                if n >= 73 {
+                    self.assign_cell(&mut region, n - 72, PadGate::flag(), F::one())?;
+                    self.enable_selector(&mut region, n - 72, PadGate::bound(), F::one())?;
+                }
+
+                let mut words = vec![];
+                for i in 0..n / 4 {
+                    let w = u32::from_le_bytes(bytes[i*4..i*4+4].try_into().unwrap());
+                    words.push(self.assign_cell(&mut region, 1 + i * 4, PadGate::w(), F::from(w as u64))?);
+                    self.enable_selector(&mut region, 1 + i * 4, PadGate::word(), F::one())?;
+                }
+                let blocks = words.chunks_exact(16).map(|c| c.to_vec().try_into().unwrap()).collect();
+                Ok((byte_cells, blocks))
+            }
+        )
+    }
+
+    /// assign the padded bytes and constrain both the padding and the
+    /// length field of the last block against the same len cell, so the
+    /// claimed length can not differ from the number of message bytes
+    pub fn assign_padding(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        len: &AssignedCell<F, F>,
+        bytes: &[u8],
+    ) -> Result<Vec<[AssignedCell<F, F>; 16]>, Error> {
+        let blocks = self.assign_padded_bytes(layouter, len, bytes)?;
+        self.assign_length(layouter, len, blocks.last().unwrap())?;
+        Ok(blocks)
+    }
+
+    /// pad the raw message bytes and assign them with assign_padding: the
+    /// marker, the zeros and the length field are all constrained against
+    /// the byte length witness len, whose bit length is range checked to
+    /// 64 bits by the length limbs, so the padding can not be forged.
+    pub fn assign_message(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        len: &AssignedCell<F, F>,
+        message: &[u8],
+    ) -> Result<Vec<[AssignedCell<F, F>; 16]>, Error> {
+        self.assign_padding(layouter, len, &pad_message(message))
+    }
+
+    /// constrain the length field (words 14 and 15) of the last block
+    /// to the little endian 64 bit encoding of len * 8
+    pub fn assign_length(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        len: &AssignedCell<F, F>,
+        last_block: &[AssignedCell<F, F>; 16],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "length field",
+            |mut region| {
+                self.bind_cell(&mut region, 0, LengthGate::len(), len)?;
+                self.bind_cell(&mut region, 0, LengthGate::lo(), &last_block[14])?;
+                self.bind_cell(&mut region, 0, LengthGate::hi(), &last_block[15])?;
+                let bits = cell_to_u64(len).ok_or(Error::Synthesis)?.wrapping_mul(8);
+                let limbs = u64_to_limbs::<F>(bits);
+                for i in 0..8 {
+                    self.assign_cell(&mut region, 0, LengthGate::limb(i), limbs[i])?;
+                }
+                self.enable_selector(&mut region, 0, LengthGate::lsel(), F::one())?;
+                Ok(())
+            }
+        )
+    }
+
+    /// pack the digest into two field elements (the low 128 bits and the
+    /// high 32 bits) so that recursive verifiers absorb fewer public inputs.
+    /// The five words are bounded to u32 on the rows after the pack row, a
+    /// word off by 2^32 would otherwise pack to the low of another digest.
+    /// With the words bounded, a field wider than 128 bits makes low unique.
+    pub fn digest_packed(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        digest: &[AssignedCell<F, F>; 5],
+    ) -> Result<[AssignedCell<F, F>; 2], Error> {
+        layouter.assign_region(
+            || "digest pack",
+            |mut region| {
+                let mut words = vec![];
+                for i in 0..5 {
+                    words.push(self.bind_cell(&mut region, 0, PackGate::w(i), &digest[i])?);
+                }
+                let low = words[0..4].iter().rev()
+                    .fold(F::zero(), |acc, w| acc * F::from(1u64 << 32) + cell_to_value(w));
+                let low = self.assign_cell(&mut region, 0, PackGate::low(), low)?;
+                self.enable_selector(&mut region, 0, PackGate::psel(), F::one())?;
+                self.assign_u32_words(&mut region, 1, digest)?;
+                Ok([low, words[4].clone()])
+            }
+        )
+    }
+
+    /// assign the initial state H0 as constants, the start state of a message.
+    /// Any other state of five u32 words can be passed as start_buf to the
+    /// assign methods, e.g. the output of a previous block or a keyed state.
+    pub fn default_state(
+        &self,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<[AssignedCell<F, F>; 5], Error> {
+        layouter.assign_region(
+            || "initial state",
+            |mut region| {
+                let mut iv = vec![];
+                for i in 0..5 {
+                    let h = F::from(H0[i] as u64);
+                    let cell = self.assign_cell(&mut region, 0, GateCell::adv(i, 0, "h"), h)?;
+                    region.constrain_constant(cell.cell(), h)?;
+                    iv.push(cell);
+                }
+                Ok(iv.try_into().unwrap())
+            }
+        )
+    }
+
+    /// chain the compression of the blocks from start_buf, or from the
+    /// constant H0 if no start state is given, and return the final state.
+    /// As in assign_prepadded the blocks must already carry their padding.
+    pub fn assign_blocks(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: Option<&[AssignedCell<F, F>; 5]>,
+        blocks: &[[AssignedCell<F, F>; 16]],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        let start_buf = match start_buf {
+            Some(start_buf) => start_buf.clone(),
+            None => self.default_state(layouter)?,
+        };
+        self.assign_prepadded(layouter, &start_buf, blocks)
+    }
+
+    /// hash blocks which already carry the RIPEMD-160 padding, chaining the
+    /// state of each block into the next one. Nothing is padded here so the
+    /// caller is responsible for the padding of the message.
+    ///
+    /// Every block is compressed in its own region whose start state is copy
+    /// constrained to the output of the previous region, so the floor planner
+    /// is free to place the blocks independently and long messages fold block
+    /// by block instead of growing a single region.
+    pub fn assign_prepadded(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        start_buf: &[AssignedCell<F, F>; 5],
+        blocks: &[[AssignedCell<F, F>; 16]],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        let mut state = start_buf.clone();
+        for block in blocks {
+            state = self.assign_content(layouter, &state, block)?;
+        }
+        Ok(state)
+    }
+
+    /// hash the 40 byte concatenation left || right of two digests, the
+    /// parent step of a RIPEMD-160 Merkle tree. The message fits a single
+    /// block so the padding words and the initial state are pinned by one
+    /// constant gate instead of going through the byte padding region.
+    pub fn hash_pair_digests(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        left: &AssignedDigest<F>,
+        right: &AssignedDigest<F>,
+    ) -> Result<AssignedDigest<F>, RMD160Error> {
+        let (iv, tail) = layouter.assign_region(
+            || "pair constants",
+            |mut region| {
+                let mut tail = vec![];
+                for i in 0..6 {
+                    tail.push(self.assign_cell(&mut region, 0, PairGate::tail(i), F::from(PAIR_TAIL[i] as u64))?);
+                }
+                let mut iv = vec![];
+                for i in 0..5 {
+                    iv.push(self.assign_cell(&mut region, 0, PairGate::iv(i), F::from(H0[i] as u64))?);
+                }
+                self.enable_selector(&mut region, 0, PairGate::pair(), F::one())?;
+                Ok((iv, tail))
+            }
+        )?;
+        let inputs = left.cells().iter()
+            .chain(right.cells().iter())
+            .chain(tail.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let digest = self.assign_content(layouter, &iv.try_into().unwrap(), &inputs.try_into().unwrap())?;
+        Ok(AssignedDigest::from_cells(digest))
+    }
+
+    /// two to one compression of a Merkle tree: the parent digest of the
+    /// little endian digest words left and right. The 40 byte message
+    /// left || right and its padding (0x80, zeros and the bit length 320)
+    /// fit in a single block, see hash_pair_digests.
+    pub fn compress_two(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        left: &[AssignedCell<F, F>; 5],
+        right: &[AssignedCell<F, F>; 5],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        let parent = self.hash_pair_digests(
+            layouter,
+            &AssignedDigest::from_cells(left.clone()),
+            &AssignedDigest::from_cells(right.clone()),
+        )?;
+        Ok(parent.into_cells())
+    }
+
+    /// pad and hash domain_tag || prefix || message from the constant initial
+    /// state. The domain tag and prefix bytes are pinned to constants, the
+    /// returned cells are the length of the whole message and the bytes of
+    /// message (the constant bytes excluded).
+    fn hash_with_prefix(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        prefix: &[u8],
+        message: &[u8],
+    ) -> Result<(AssignedCell<F, F>, Vec<AssignedCell<F, F>>, AssignedDigest<F>), RMD160Error> {
+        let prefix = [&self.domain_tag[..], prefix].concat();
+        let full = [&prefix[..], message].concat();
+        let padded = message_blocks(&full).iter().flatten()
+            .flat_map(|w| w.to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        let (len, iv) = layouter.assign_region(
+            || "message constants",
+            |mut region| {
+                // the length is a constant of the circuit like the prefix
+                // bytes, the keys of an n byte message only prove n bytes
+                let n = F::from(full.len() as u64);
+                let len = self.assign_cell(&mut region, 0, GateCell::adv(0, 0, "len"), n)?;
+                region.constrain_constant(len.cell(), n)?;
+                let mut iv = vec![];
+                for i in 0..5 {
+                    let h = F::from(H0[i] as u64);
+                    let cell = self.assign_cell(&mut region, 0, GateCell::adv(i + 1, 0, "h"), h)?;
+                    region.constrain_constant(cell.cell(), h)?;
+                    iv.push(cell);
+                }
+                Ok((len, iv))
+            }
+        )?;
+        let (bytes, blocks) = self.assign_padded_cells(layouter, &len, &padded)?;
+        self.assign_length(layouter, &len, blocks.last().unwrap())?;
+        layouter.assign_region(
+            || "message prefix",
+            |mut region| {
+                for (cell, b) in bytes.iter().zip(prefix.iter()) {
+                    region.constrain_constant(cell.cell(), F::from(*b as u64))?;
+                }
+                Ok(())
+            }
+        )?;
+
+        let digest = self.assign_prepadded(layouter, &iv.try_into().unwrap(), &blocks)?;
+        let message_bytes = bytes[prefix.len()..full.len()].to_vec();
+        Ok((len, message_bytes, AssignedDigest::from_cells(digest)))
+    }
+
+    /// pad and hash prefix || message where the prefix bytes are copied from
+    /// assigned cells, the length is the constant number of bytes. Returns
+    /// the byte cells of the message and the digest.
+    fn hash_after_cells(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        prefix: &[AssignedCell<F, F>],
+        message: &[u8],
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedDigest<F>), RMD160Error> {
+        let full = prefix.iter().map(|c| cell_to_u32(c) as u8).chain(message.iter().copied()).collect::<Vec<_>>();
+        let len = layouter.assign_region(
+            || "prefixed length",
+            |mut region| {
+                let len = F::from(full.len() as u64);
+                let cell = self.assign_cell(&mut region, 0, GateCell::adv(0, 0, "len"), len)?;
+                region.constrain_constant(cell.cell(), len)?;
+                Ok(cell)
+            }
+        )?;
+        let (bytes, blocks) = self.assign_padded_cells(layouter, &len, &pad_message(&full))?;
+        self.assign_length(layouter, &len, blocks.last().unwrap())?;
+        layouter.assign_region(
+            || "prefix cells",
+            |mut region| {
+                for (cell, byte) in prefix.iter().zip(bytes.iter()) {
+                    region.constrain_equal(cell.cell(), byte.cell())?;
+                }
+                Ok(())
+            }
+        )?;
+        let digest = self.assign_blocks(layouter, None, &blocks)?;
+        Ok((bytes[prefix.len()..full.len()].to_vec(), AssignedDigest::from_cells(digest)))
+    }
+
+    /// the ipad and opad bytes of a key of at most HMAC_BLOCK_LEN bytes,
+    /// the key is extended with constant zeros to the block length
+    fn assign_hmac_key(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        key: &[AssignedCell<F, F>],
+    ) -> Result<(Vec<AssignedCell<F, F>>, Vec<AssignedCell<F, F>>), Error> {
+        layouter.assign_region(
+            || "hmac key",
+            |mut region| {
+                let mut ipad = vec![];
+                let mut opad = vec![];
+                for j in 0..HMAC_BLOCK_LEN {
+                    let row = 2 * j;
+                    let k = match key.get(j) {
+                        Some(cell) => {
+                            self.bind_cell(&mut region, row, HmacGate::k(), cell)?;
+                            cell_to_u32(cell) as u8
+                        },
+                        None => {
+                            let cell = self.assign_cell(&mut region, row, HmacGate::k(), F::zero())?;
+                            region.constrain_constant(cell.cell(), F::zero())?;
+                            0
+                        },
+                    };
+                    for i in 0..8 {
+                        self.assign_cell(&mut region, row, HmacGate::bit(i), F::from(((k >> i) & 1) as u64))?;
+                    }
+                    ipad.push(self.assign_cell(&mut region, row, HmacGate::ipad(), F::from((k ^ HMAC_IPAD) as u64))?);
+                    opad.push(self.assign_cell(&mut region, row, HmacGate::opad(), F::from((k ^ HMAC_OPAD) as u64))?);
+                    self.enable_selector(&mut region, row, HmacGate::key(), F::one())?;
+                }
+                Ok((ipad, opad))
+            }
+        )
+    }
+
+    /// HMAC-RIPEMD160 of the message under the assigned key bytes:
+    /// H((key ^ opad) || H((key ^ ipad) || message)). The key bytes are
+    /// decomposed into bits, which also range checks them, and XORed with
+    /// the pad constants under constraints. Keys longer than HMAC_BLOCK_LEN
+    /// bytes have to be hashed beforehand as HMAC specifies, they are
+    /// rejected with RMD160Error::InputLength. The domain tag of the chip is not
+    /// applied. Returns the message byte cells and the MAC.
+    pub fn hmac_rmd160(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        key: &[AssignedCell<F, F>],
+        message: &[u8],
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedDigest<F>), RMD160Error> {
+        if key.len() > HMAC_BLOCK_LEN {
+            return Err(RMD160Error::InputLength(key.len()));
+        }
+        let (ipad, opad) = self.assign_hmac_key(layouter, key)?;
+        let (message_bytes, inner) = self.hash_after_cells(layouter, &ipad, message)?;
+        let (inner_bytes, mac) = self.hash_after_cells(layouter, &opad, &inner.bytes())?;
+
+        // the bytes 4j .. 4j + 3 of the outer message pack to the inner word j
+        layouter.assign_region(
+            || "hmac inner digest",
+            |mut region| {
+                for (j, word) in inner.cells().iter().enumerate() {
+                    for i in 0..4 {
+                        self.bind_cell(&mut region, 4 * j, PadGate::byte(i), &inner_bytes[4 * j + i])?;
+                    }
+                    self.bind_cell(&mut region, 4 * j, PadGate::w(), word)?;
+                    self.enable_selector(&mut region, 4 * j, PadGate::word(), F::one())?;
+                }
+                Ok(())
+            }
+        )?;
+        Ok((message_bytes, mac))
+    }
+
+    /// pad and hash the message bytes, prepended with the domain tag of the
+    /// chip. Any length is accepted, the empty message included: the blocks
+    /// are chained from H0 and the padding spills into an extra block once
+    /// the length field no longer fits.
+    pub fn hash_bytes(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        message: &[u8],
+    ) -> Result<AssignedDigest<F>, RMD160Error> {
+        let (_, _, digest) = self.hash_with_prefix(layouter, &[], message)?;
+        Ok(digest)
+    }
+
+    /// hash tag || d0 || d1 || .. for a constant tag byte. The tag and the
+    /// message length are constants and the message bytes are packed back
+    /// into the digest words, thus the prover can not pass a digest hashed
+    /// under one tag off as a digest of another tag.
+    pub fn hash_tagged_digests(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        tag: u8,
+        digests: &[&AssignedDigest<F>],
+    ) -> Result<AssignedDigest<F>, RMD160Error> {
+        let message = digests.iter().flat_map(|d| d.bytes()).collect::<Vec<u8>>();
+        let (len, bytes, digest) = self.hash_with_prefix(layouter, &[tag], &message)?;
+
+        // the bytes 4j .. 4j + 3 of the message pack to the word j
+        layouter.assign_region(
+            || "tagged digests",
+            |mut region| {
+                let n = self.domain_tag.len() + 1 + message.len();
+                region.constrain_constant(len.cell(), F::from(n as u64))?;
+                let words = digests.iter().flat_map(|d| d.cells().iter());
+                for (j, word) in words.enumerate() {
+                    for i in 0..4 {
+                        self.bind_cell(&mut region, 4 * j, PadGate::byte(i), &bytes[4 * j + i])?;
+                    }
+                    self.bind_cell(&mut region, 4 * j, PadGate::w(), word)?;
+                    self.enable_selector(&mut region, 4 * j, PadGate::word(), F::one())?;
+                }
+                Ok(())
+            }
+        )?;
+        Ok(digest)
+    }
+
+    /// order two digests by a boolean cell: (a, b) when bit is 0 and
+    /// (b, a) when bit is 1, the bit itself is constrained to be boolean
+    pub fn conditional_swap(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bit: &AssignedCell<F, F>,
+        a: &AssignedDigest<F>,
+        b: &AssignedDigest<F>,
+    ) -> Result<(AssignedDigest<F>, AssignedDigest<F>), Error> {
+        let swapped = field_to_u64_checked(&cell_to_value(bit)) == Some(1);
+        layouter.assign_region(
+            || "conditional swap",
+            |mut region| {
+                let mut l = vec![];
+                let mut r = vec![];
+                for i in 0..5 {
+                    self.bind_cell(&mut region, i, SwapGate::s(), bit)?;
+                    let ac = self.bind_cell(&mut region, i, SwapGate::a(), &a.cells()[i])?;
+                    let bc = self.bind_cell(&mut region, i, SwapGate::b(), &b.cells()[i])?;
+                    let (lv, rv) = if swapped { (bc, ac) } else { (ac, bc) };
+                    l.push(self.assign_cell(&mut region, i, SwapGate::l(), cell_to_value(&lv))?);
+                    r.push(self.assign_cell(&mut region, i, SwapGate::r(), cell_to_value(&rv))?);
+                    self.enable_selector(&mut region, i, SwapGate::swap(), F::one())?;
+                }
+                Ok((
+                    AssignedDigest::from_cells(l.try_into().unwrap()),
+                    AssignedDigest::from_cells(r.try_into().unwrap()),
+                ))
+            }
+        )
+    }
+
+    /// fold leaf up to the root of its tree along path, one entry
+    /// (bit, sibling) per level from the leaf upwards. The leaf is hashed
+    /// under MERKLE_LEAF_TAG, then the node and its sibling are ordered by
+    /// conditional_swap, the sibling comes first when bit is 1 (the node is
+    /// the right child), and hashed under MERKLE_NODE_TAG. This is the tree
+    /// of host::rmd160::merkle_root, an internal node can not pass as a leaf.
+    pub fn merkle_path_root(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        leaf: &AssignedDigest<F>,
+        path: &[(AssignedCell<F, F>, AssignedDigest<F>)],
+    ) -> Result<AssignedDigest<F>, RMD160Error> {
+        let mut node = self.hash_tagged_digests(layouter, MERKLE_LEAF_TAG, &[leaf])?;
+        for (bit, sibling) in path {
+            let (left, right) = self.conditional_swap(layouter, bit, &node, sibling)?;
+            node = self.hash_tagged_digests(layouter, MERKLE_NODE_TAG, &[&left, &right])?;
+        }
+        Ok(node)
+    }
+
+    /// merkle_path_root copy constrained to root
+    pub fn verify_merkle_path(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        leaf: &AssignedDigest<F>,
+        path: &[(AssignedCell<F, F>, AssignedDigest<F>)],
+        root: &AssignedDigest<F>,
+    ) -> Result<(), RMD160Error> {
+        let node = self.merkle_path_root(layouter, leaf, path)?;
+        layouter.assign_region(
+            || "merkle root",
+            |mut region| node.constrain_equal(&mut region, root)
+        )?;
+        Ok(())
+    }
+
+    /// continue hashing from an intermediate state published by a previous
+    /// proof. The five state words are read from the instance column at
+    /// state_offset, so the verifier links the two proofs by feeding the
+    /// public output state of the first one into the second one.
+    pub fn assign_resumed(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        instance: Column<Instance>,
+        state_offset: usize,
+        blocks: &[[AssignedCell<F, F>; 16]],
+    ) -> Result<[AssignedCell<F, F>; 5], RMD160Error> {
+        let state = layouter.assign_region(
+            || "resumed state",
+            |mut region| {
+                let mut cells = vec![];
+                for i in 0..5 {
+                    cells.push(region.assign_advice_from_instance(
+                        || format!("state {}", i),
+                        instance,
+                        state_offset + i,
+                        self.config.witness[i],
+                        0,
+                    )?);
+                }
+                Ok(cells.try_into().unwrap())
+            }
+        )?;
+        self.assign_prepadded(layouter, &state, blocks)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::{Bn256, Fr, G1Affine};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use halo2_proofs::poly::kzg::commitment::{KZGCommitmentScheme, ParamsKZG};
+    use halo2_proofs::poly::kzg::multiopen::{ProverGWC, VerifierGWC};
+    use halo2_proofs::poly::kzg::strategy::SingleStrategy;
+    use halo2_proofs::transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    };
+    use rand::SeedableRng;
+    use std::ops::{Deref, DerefMut};
+
+    use halo2_proofs::{
+        arithmetic::{Field, FieldExt},
+        circuit::{Value, Chip, Layouter, AssignedCell, SimpleFloorPlanner},
+        plonk::{
+            Advice, Circuit, Column, ConstraintSystem, Error, Instance,
+        },
+    };
+
+    use super::RMD160Chip;
+    use super::{RMD160Config, RMD160ConfigBuilder};
+    use super::{AssignedDigest, RMD160Hasher, digest_to_bytes};
+    use crate::utils::{cell_to_bytes, cell_to_u32, cell_to_value, digest_bytes_to_words, digest_from_hex, u32_to_limbs};
+    use super::{carry_split, get_witnesses, rotate_multipliers, Carry};
+    use super::{block_witnesses, precompute_all_witnesses, step_witness};
+    use super::{check_round_witness, RoundEquation, WitnessMismatch};
+    use super::{ROWS_PER_ROUND, ROWS_PER_COMPRESS};
+    use super::{CompressGate, GateCell, RoundGate, WordGate, WITNESS_COLS};
+    use super::{RangeTable, RangeTableConfig, RoundFunctionTable};
+    use crate::host::rmd160::{O, R, PO, PR, ROUNDS_OFFSET, PROUNDS_OFFSET};
+    use crate::host::rmd160::{Combination, HashSpec, Rmd160Spec, RoundSchedule};
+    use crate::utils::field_to_u64;
+    use super::{check_field_bits, RMD160Error, RMD160Field, MIN_FIELD_BITS};
+    use crate::host::rmd160::{
+        H0, compress, compress_block, compress_lines, hash_prepadded, message_blocks,
+        hmac_rmd160, pad_message, rmd160_block_words, rmd160_digest,
+        merkle_leaf, merkle_node, merkle_root,
+    };
+
+    const K: u32 = 16;
+
+    #[derive(Clone, Debug)]
+    pub struct HelperChipConfig {
+        limb: Column<Advice>
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct HelperChip {
+        config: HelperChipConfig
+    }
+
+    impl Chip<Fr> for HelperChip {
+        type Config = HelperChipConfig;
+        type Loaded = ();
+
+        fn config(&self) -> &Self::Config {
+            &self.config
+        }
+
+        fn loaded(&self) -> &Self::Loaded {
+            &()
+        }
+    }
+
+    impl HelperChip {
         fn new(config: HelperChipConfig) -> Self {
             HelperChip{
                 config,
             }
         }
 
-        fn configure(cs: &mut ConstraintSystem<Fr>) -> HelperChipConfig {
-            let limb= cs.advice_column();
-            cs.enable_equality(limb);
-            HelperChipConfig {
-                limb,
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> HelperChipConfig {
+            let limb= cs.advice_column();
+            cs.enable_equality(limb);
+            HelperChipConfig {
+                limb,
+            }
+        }
+
+        fn assign_w(
+            &self,
+            layouter: &mut impl Layouter<Fr>,
+            inputs: &[u32; 5],
+            offset: usize,
+        ) -> Result<[AssignedCell<Fr, Fr>; 5], Error> {
+            layouter.assign_region(
+                || "leaf layer",
+                |mut region| {
+                    let mut r = vec![];
+                    for round in 0..5 {
+                        r.push(region.assign_advice(
+                            || format!("assign w"),
+                            self.config.limb,
+                            offset + round,
+                            || Value::known(Fr::from(inputs[round] as u64))
+                        )?);
+                    }
+                    Ok(r.try_into().unwrap())
+                }
+            )
+        }
+
+        fn assign_value(
+            &self,
+            layouter: &mut impl Layouter<Fr>,
+            value: u64,
+        ) -> Result<AssignedCell<Fr, Fr>, Error> {
+            layouter.assign_region(
+                || "assign value",
+                |mut region| {
+                    region.assign_advice(
+                        || format!("assign value"),
+                        self.config.limb,
+                        0,
+                        || Value::known(Fr::from(value))
+                    )
+                }
+            )
+        }
+
+        fn assign_inputs(
+            &self,
+            layouter: &mut impl Layouter<Fr>,
+            inputs: &[u32; 16],
+            offset: usize
+        ) -> Result<[AssignedCell<Fr, Fr>; 16], Error> {
+            self.assign_field_inputs(layouter, &inputs.map(|x| Fr::from(x as u64)), offset)
+        }
+
+        fn assign_field_inputs(
+            &self,
+            layouter: &mut impl Layouter<Fr>,
+            inputs: &[Fr; 16],
+            offset: usize
+        ) -> Result<[AssignedCell<Fr, Fr>; 16], Error> {
+            layouter.assign_region(
+                || "leaf layer",
+                |mut region| {
+                    let mut r = vec![];
+                    for i in 0..16 {
+                        let cell = region.assign_advice(
+                            || format!("assign input"),
+                            self.config.limb,
+                            offset + i,
+                            || Value::known(inputs[i])
+                        )?;
+                        r.push(cell);
+                    }
+                    Ok(r.try_into().unwrap())
+                }
+            )
+        }
+    }
+
+    /// hash N independent single block messages and expose the N*5 digest words
+    #[derive(Clone, Debug)]
+    struct RMD160Circuit<const N: usize> {
+        inputs: [[u32; 16]; N],
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestConfig {
+        rmd160config: RMD160Config,
+        helperconfig: HelperChipConfig,
+        instance: Column<Instance>,
+    }
+
+    fn test_config(meta: &mut ConstraintSystem<Fr>, builder: RMD160ConfigBuilder) -> TestConfig {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        TestConfig {
+            rmd160config: builder.configure(meta),
+            helperconfig: HelperChip::configure(meta),
+            instance,
+        }
+    }
+
+    impl<const N: usize> Circuit<Fr> for RMD160Circuit<N> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            RMD160Circuit { inputs: [[0; 16]; N] }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            Self::Config {
+               rmd160config: RMD160Chip::<Fr>::configure(meta),
+               helperconfig: HelperChip::configure(meta),
+               instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            rmd160chip.load_range_table(&mut layouter)?;
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            for n in 0..N {
+                let w= helperchip.assign_w(&mut layouter, &H0, 0)?;
+                let input = helperchip.assign_inputs(&mut layouter, &self.inputs[n], 0)?;
+                let r = rmd160chip.assign_content(&mut layouter, &w, &input)?;
+                let digest = AssignedDigest::from_cells(r.clone());
+                digest.verify_against_instance(&mut layouter, config.instance, n * 5)?;
+                assert_eq!(digest.bytes(), rmd160_words_bytes(&r));
+                let cells = digest.into_cells();
+                for i in 0..5 {
+                    assert_eq!(cells[i].cell(), r[i].cell());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// The part a test circuit adds on top of a chip configured as in
+    /// RMD160Circuit, with the range table already loaded. ChipCircuit runs
+    /// it, so the tests only spell out their own synthesis.
+    trait ChipTest: Clone + Default {
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error>;
+    }
+
+    /// the circuit of a ChipTest, derefs to the test for its fields
+    #[derive(Clone, Debug, Default)]
+    struct ChipCircuit<T>(T);
+
+    impl<T> Deref for ChipCircuit<T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.0
+        }
+    }
+
+    impl<T> DerefMut for ChipCircuit<T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+    }
+
+    impl<T: ChipTest> Circuit<Fr> for ChipCircuit<T> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            ChipCircuit(self.0.without_witnesses())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            RMD160Circuit::<1>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            rmd160chip.load_range_table(&mut layouter)?;
+            self.0.synthesize(&rmd160chip, config, &mut layouter)
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct PrepaddedCircuit {
+        blocks: Vec<[u32; 16]>,
+    }
+
+    impl ChipTest for PrepaddedCircuit {
+        fn without_witnesses(&self) -> Self {
+            PrepaddedCircuit { blocks: vec![[0; 16]; self.blocks.len()] }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let mut blocks = vec![];
+            for block in self.blocks.iter() {
+                blocks.push(helperchip.assign_inputs(layouter, block, 0)?);
+            }
+            let r = rmd160chip.assign_prepadded(layouter, &w, &blocks)?;
+            for i in 0..5 {
+                layouter.constrain_instance(r[i].cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// continue from the intermediate state at instance rows 0..5 and
+    /// expose the final state at rows 5..10
+    #[derive(Clone, Debug, Default)]
+    struct ResumedCircuit {
+        blocks: Vec<[u32; 16]>,
+    }
+
+    impl ChipTest for ResumedCircuit {
+        fn without_witnesses(&self) -> Self {
+            ResumedCircuit { blocks: vec![[0; 16]; self.blocks.len()] }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let mut blocks = vec![];
+            for block in self.blocks.iter() {
+                blocks.push(helperchip.assign_inputs(layouter, block, 0)?);
+            }
+            let r = rmd160chip.assign_resumed(layouter, config.instance, 0, &blocks)?;
+            AssignedDigest::from_cells(r).verify_against_instance(layouter, config.instance, 5)
+        }
+    }
+
+    #[test]
+    fn test_resumed_blocks() {
+        let message = (0..150u32).map(|x| (x * 13) as u8).collect::<Vec<_>>();
+        let blocks = message_blocks(&message);
+        assert_eq!(blocks.len(), 3);
+        let state = rmd160_block_words(H0, blocks[0]);
+        let state_instances = state.iter().map(|w| Fr::from(*w as u64)).collect::<Vec<_>>();
+
+        // the first proof publishes the state after the first block
+        let first = ChipCircuit(PrepaddedCircuit { blocks: blocks[..1].to_vec() });
+        let prover = MockProver::run(K, &first, vec![state_instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the second proof consumes it and ends with the digest of the message
+        let second = ChipCircuit(ResumedCircuit { blocks: blocks[1..].to_vec() });
+        let mut instances = state_instances.clone();
+        instances.extend(digest_instances(rmd160_digest(&message)));
+        let prover = MockProver::run(K, &second, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // a different intermediate state does not lead to the same digest
+        instances[0] += Fr::one();
+        let prover = MockProver::run(K, &second, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_rmd160_fold_four_blocks() {
+        let message = (0..200u32).map(|x| (x * 7) as u8).collect::<Vec<_>>();
+        let blocks = message_blocks(&message);
+        assert_eq!(blocks.len(), 4);
+        let test_circuit = ChipCircuit(PrepaddedCircuit { blocks });
+        let instances = digest_instances(rmd160_digest(&message));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct LengthCircuit {
+        blocks: Vec<[u32; 16]>,
+        len: u64,
+    }
+
+    impl ChipTest for LengthCircuit {
+        fn without_witnesses(&self) -> Self {
+            LengthCircuit { blocks: vec![[0; 16]; self.blocks.len()], len: 0 }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let len = helperchip.assign_value(layouter, self.len)?;
+            let mut blocks = vec![];
+            for block in self.blocks.iter() {
+                blocks.push(helperchip.assign_inputs(layouter, block, 0)?);
+            }
+            rmd160chip.assign_length(layouter, &len, blocks.last().unwrap())?;
+            let r = rmd160chip.assign_prepadded(layouter, &w, &blocks)?;
+            AssignedDigest::from_cells(r).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_length_field() {
+        for len in [0, 3, 55, 56, 64, 119] {
+            let message = vec![0x5a; len];
+            let test_circuit = ChipCircuit(LengthCircuit { blocks: message_blocks(&message), len: len as u64 });
+            let instances = digest_instances(rmd160_digest(&message));
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_wrong_length_field() {
+        let message = vec![0x5a; 20];
+        let blocks = message_blocks(&message);
+        // claimed length does not match the length words
+        let test_circuit = ChipCircuit(LengthCircuit { blocks: blocks.clone(), len: 21 });
+        let instances = digest_instances(rmd160_digest(&message));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // length words tampered while the digest follows the tampered block
+        let mut tampered = blocks;
+        tampered[0][14] = 8 * 21;
+        let instances = digest_instances(hash_prepadded(&tampered));
+        let test_circuit = ChipCircuit(LengthCircuit { blocks: tampered, len: 20 });
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct PaddingCircuit {
+        bytes: Vec<u8>,
+        len: u64,
+    }
+
+    impl ChipTest for PaddingCircuit {
+        fn without_witnesses(&self) -> Self {
+            PaddingCircuit { bytes: vec![0; self.bytes.len()], len: 0 }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let len = helperchip.assign_value(layouter, self.len)?;
+            let blocks = rmd160chip.assign_padding(layouter, &len, &self.bytes)?;
+            let r = rmd160chip.assign_prepadded(layouter, &w, &blocks)?;
+            AssignedDigest::from_cells(r).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    fn padded_bytes(message: &[u8]) -> Vec<u8> {
+        message_blocks(message).iter().flatten().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    fn bytes_digest(bytes: &[u8]) -> [u8; 20] {
+        let blocks = bytes.chunks_exact(64)
+            .map(|b| b.chunks_exact(4)
+                .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+                .collect::<Vec<_>>().try_into().unwrap())
+            .collect::<Vec<[u32; 16]>>();
+        hash_prepadded(&blocks)
+    }
+
+    #[test]
+    fn test_padding_constraints() {
+        for len in [0, 3, 55, 56, 64] {
+            let message = (0..len).map(|x| x as u8 ^ 0x3c).collect::<Vec<_>>();
+            let test_circuit = ChipCircuit(PaddingCircuit { bytes: padded_bytes(&message), len: len as u64 });
+            let instances = digest_instances(rmd160_digest(&message));
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_padding_partial_block() {
+        for n in [0, 63, 65] {
+            let test_circuit = ChipCircuit(PaddingCircuit { bytes: vec![0; n], len: 0 });
+            assert!(MockProver::run(K, &test_circuit, vec![vec![]]).is_err(), "{} bytes", n);
+        }
+    }
+
+    #[test]
+    fn test_adversarial_padding_bytes() {
+        let message = vec![0x11; 20];
+        let bytes = padded_bytes(&message);
+        // data hidden in the zero run, a wrong marker and a missing marker
+        for (pos, value) in [(30, 0x01), (20, 0x81), (20, 0x00)] {
+            let mut tampered = bytes.clone();
+            tampered[pos] = value;
+            let test_circuit = ChipCircuit(PaddingCircuit { bytes: tampered.clone(), len: 20 });
+            let instances = digest_instances(bytes_digest(&tampered));
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert!(prover.verify().is_err(), "byte {} set to {}", pos, value);
+        }
+    }
+
+    #[test]
+    fn test_shorter_length_claim() {
+        let message = (0..20u8).collect::<Vec<_>>();
+        let bytes = padded_bytes(&message);
+
+        // claim 12 bytes while the message bytes run up to 20
+        let test_circuit = ChipCircuit(PaddingCircuit { bytes: bytes.clone(), len: 12 });
+        let instances = digest_instances(rmd160_digest(&message));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // same claim with the length field rewritten to 12 bytes as well
+        let mut tampered = bytes;
+        tampered[56..64].copy_from_slice(&(12u64 * 8).to_le_bytes());
+        let test_circuit = ChipCircuit(PaddingCircuit { bytes: tampered.clone(), len: 12 });
+        let instances = digest_instances(bytes_digest(&tampered));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_two_block_message() {
+        // 64 bytes leave no room for the length field in the first block
+        let message = (0..64u8).map(|x| b'a' + x % 26).collect::<Vec<_>>();
+        let bytes = padded_bytes(&message);
+        assert_eq!(bytes.len(), 128);
+        assert_eq!(bytes[64], 0x80);
+        let test_circuit = ChipCircuit(PaddingCircuit { bytes, len: 64 });
+        let instances = digest_instances(rmd160_digest(&message));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_padding_block_boundary() {
+        // 55 bytes take marker and length in one block, 56 bytes spill over
+        for (len, num_blocks) in [(55, 1), (56, 2)] {
+            let message = vec![0x5a; len];
+            let bytes = padded_bytes(&message);
+            assert_eq!(bytes.len(), num_blocks * 64);
+            assert_eq!(bytes[len], 0x80);
+            let test_circuit = ChipCircuit(PaddingCircuit { bytes, len: len as u64 });
+            let instances = digest_instances(rmd160_digest(&message));
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "length {}", len);
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct PairCircuit {
+        left: [u8; 20],
+        right: [u8; 20],
+    }
+
+    impl ChipTest for PairCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let left = helperchip.assign_w(layouter, &digest_bytes_to_words(self.left), 0)?;
+            let right = helperchip.assign_w(layouter, &digest_bytes_to_words(self.right), 0)?;
+            let parent = rmd160chip.hash_pair_digests(
+                layouter,
+                &AssignedDigest::from_cells(left),
+                &AssignedDigest::from_cells(right),
+            )?;
+            parent.verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_hash_pair_digests() {
+        let left = rmd160_digest(b"left");
+        let right = rmd160_digest(b"right");
+        let test_circuit = ChipCircuit(PairCircuit { left, right });
+        let message = [left, right].concat();
+        let instances = digest_instances(rmd160_digest(&message));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the order of the children matters
+        let swapped = digest_instances(rmd160_digest(&[right, left].concat()));
+        let prover = MockProver::run(K, &test_circuit, vec![swapped]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// one block from the message region, recording the words the x cells
+    /// of both lines read in the order of the steps
+    #[derive(Clone, Debug, Default)]
+    struct MessageWordsCircuit {
+        inputs: [u32; 16],
+        observed: std::cell::RefCell<[Vec<u32>; 2]>,
+    }
+
+    impl ChipTest for MessageWordsCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let message = rmd160chip.assign_message_words(layouter, &input)?;
+            let digest = layouter.assign_region(
+                || "message words block",
+                |mut region| {
+                    let ([r1, r2], _, xs, offset) = rmd160chip.assign_lines::<Rmd160Spec>(&mut region, 0, &w, &message)?;
+                    *self.observed.borrow_mut() = xs.map(|line| line.iter().map(cell_to_u32).collect());
+                    let (digest, _) = rmd160chip.assign_combine::<Rmd160Spec>(&mut region, offset, &w, &r1, &r2)?;
+                    Ok(digest)
+                }
+            )?;
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    /// one block from a chosen start state with the compress step assigned
+    /// by hand, tamper overwrites the copy of start word 0 in the a lane
+    #[derive(Clone, Debug, Default)]
+    struct ChainingCircuit {
+        state: [u32; 5],
+        words: [u32; 16],
+        tamper: bool,
+    }
+
+    impl ChipTest for ChainingCircuit {
+        fn without_witnesses(&self) -> Self {
+            ChainingCircuit { tamper: self.tamper, ..Self::default() }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &self.state, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.words, 0)?;
+            let digest = layouter.assign_region(
+                || "chained block",
+                |mut region| {
+                    let ([r1, r2], _, _, offset) = rmd160chip.assign_lines::<Rmd160Spec>(&mut region, 0, &w, &input)?;
+                    let digest = rmd160chip.assign_compress(&mut region, offset, &w, &r1, &r2)?;
+                    if self.tamper {
+                        let a = Fr::from(self.state[0] as u64 + 1);
+                        rmd160chip.assign_cell(&mut region, offset, CompressGate::a(), a)?;
+                    }
+                    Ok(digest)
+                }
+            )?;
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_compress_uses_chaining_value() {
+        let state = [0x0123_4567, 0x89ab_cdef, 0xfedc_ba98, 0x7654_3210, 0xf0e1_d2c3];
+        let words = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let instances = rmd160_block_words(state, words).map(|x| Fr::from(x as u64)).to_vec();
+        assert_ne!(instances, host_digest(&words));
+
+        let test_circuit = ChipCircuit(ChainingCircuit { state, words, tamper: false });
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the digest from H0 does not verify for another start state
+        let prover = MockProver::run(K, &test_circuit, vec![host_digest(&words)]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the a lane no longer equals the start word it was copied from
+        let test_circuit = ChipCircuit(ChainingCircuit { state, words, tamper: true });
+        let errors = MockProver::run(K, &test_circuit, vec![instances]).unwrap().verify().unwrap_err();
+        assert!(errors.iter().any(|e| matches!(e, halo2_proofs::dev::VerifyFailure::Permutation { .. })), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_lines_read_message_words() {
+        let inputs = std::array::from_fn(|i| 0x0101_0101 * (i as u32 + 1));
+        let test_circuit = ChipCircuit(MessageWordsCircuit { inputs, observed: Default::default() });
+        let prover = MockProver::run(K, &test_circuit, vec![host_digest(&inputs)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // undo the word order of each line: both see the same 16 words
+        let schedule = RoundSchedule::rmd160();
+        let observed = test_circuit.observed.borrow();
+        for pround in [false, true] {
+            assert_eq!(observed[pround as usize].len(), 80);
+            for (step, x) in observed[pround as usize].iter().enumerate() {
+                assert_eq!(*x, inputs[schedule.order(pround, step / 16)[step % 16]]);
+            }
+        }
+        let mut left = observed[0].clone();
+        let mut right = observed[1].clone();
+        left.sort();
+        right.sort();
+        assert_eq!(left, right);
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct TreeCircuit {
+        leaves: [[u8; 20]; 4],
+    }
+
+    impl ChipTest for TreeCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let mut level = vec![];
+            for leaf in self.leaves.iter() {
+                level.push(helperchip.assign_w(layouter, &digest_bytes_to_words(*leaf), 0)?);
+            }
+            while level.len() > 1 {
+                let mut parents = vec![];
+                for pair in level.chunks_exact(2) {
+                    parents.push(rmd160chip.compress_two(layouter, &pair[0], &pair[1])?);
+                }
+                level = parents;
+            }
+            let root = AssignedDigest::from_cells(level.pop().unwrap());
+            root.verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    fn host_tree_root(leaves: &[[u8; 20]]) -> [u8; 20] {
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            level = level.chunks_exact(2)
+                .map(|pair| rmd160_digest(&[pair[0], pair[1]].concat()))
+                .collect();
+        }
+        level[0]
+    }
+
+    #[test]
+    fn test_compress_two_tree_root() {
+        let leaves = [b"a", b"b", b"c", b"d"].map(|l| rmd160_digest(l));
+        let test_circuit = ChipCircuit(TreeCircuit { leaves });
+        let instances = digest_instances(host_tree_root(&leaves));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // swapping two leaves changes the root
+        let swapped = [leaves[1], leaves[0], leaves[2], leaves[3]];
+        let instances = digest_instances(host_tree_root(&swapped));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct MerklePathCircuit {
+        leaf: [u8; 20],
+        path: Vec<(bool, [u8; 20])>,
+        root: [u8; 20],
+    }
+
+    impl ChipTest for MerklePathCircuit {
+        fn without_witnesses(&self) -> Self {
+            MerklePathCircuit { path: vec![(false, [0; 20]); self.path.len()], ..Self::default() }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let leaf = helperchip.assign_w(layouter, &digest_bytes_to_words(self.leaf), 0)?;
+            let mut path = vec![];
+            for (bit, sibling) in self.path.iter() {
+                let bit = helperchip.assign_value(layouter, *bit as u64)?;
+                let sibling = helperchip.assign_w(layouter, &digest_bytes_to_words(*sibling), 0)?;
+                path.push((bit, AssignedDigest::from_cells(sibling)));
+            }
+            let root = helperchip.assign_w(layouter, &digest_bytes_to_words(self.root), 0)?;
+            rmd160chip.verify_merkle_path(
+                layouter,
+                &AssignedDigest::from_cells(leaf),
+                &path,
+                &AssignedDigest::from_cells(root),
+            )?;
+            Ok(())
+        }
+    }
+
+    /// the path of leaf index in the tagged tree of 16 leaves and the root
+    /// of the tree, bit i is set when the node at level i is the right child
+    fn host_merkle_path(leaves: &[[u8; 20]; 16], index: usize) -> (Vec<(bool, [u8; 20])>, [u8; 20]) {
+        let mut level = leaves.map(|leaf| merkle_leaf(&leaf)).to_vec();
+        let mut path = vec![];
+        let mut index = index;
+        while level.len() > 1 {
+            path.push((index & 1 == 1, level[index ^ 1]));
+            level = level.chunks_exact(2)
+                .map(|pair| merkle_node(&pair[0], &pair[1]))
+                .collect();
+            index >>= 1;
+        }
+        (path, level[0])
+    }
+
+    #[test]
+    fn test_verify_merkle_path() {
+        let leaves: [[u8; 20]; 16] = std::array::from_fn(|i| rmd160_digest(&[i as u8]));
+        let (_, root) = host_merkle_path(&leaves, 0);
+        // 0b0101 and 0b1010 put the node on both sides at every level
+        for index in [0b0101, 0b1010] {
+            let (path, _) = host_merkle_path(&leaves, index);
+            assert_eq!(path.len(), 4);
+            // the root of MerkleCircuit for the same leaf and path
+            let (bits, siblings): (Vec<_>, Vec<_>) = path.iter().cloned().unzip();
+            assert_eq!(merkle_root(&leaves[index], &siblings, &bits), root);
+            let test_circuit = ChipCircuit(MerklePathCircuit { leaf: leaves[index], path, root });
+            let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // a flipped direction bit or a wrong root is rejected
+        let (mut path, _) = host_merkle_path(&leaves, 0b0101);
+        path[2].0 = !path[2].0;
+        let test_circuit = ChipCircuit(MerklePathCircuit { leaf: leaves[0b0101], path, root });
+        let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        let (path, _) = host_merkle_path(&leaves, 0b0101);
+        let test_circuit = ChipCircuit(MerklePathCircuit { leaf: leaves[0b0101], path, root: leaves[0] });
+        let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct TaggedCircuit {
+        tag: Vec<u8>,
+        message: Vec<u8>,
+    }
+
+    impl Circuit<Fr> for TaggedCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TaggedCircuit { tag: self.tag.clone(), message: vec![0; self.message.len()] }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            RMD160Circuit::<1>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config)
+                .with_domain_tag(&self.tag);
+            rmd160chip.load_range_table(&mut layouter)?;
+            let digest = rmd160chip.hash_bytes(&mut layouter, &self.message)?;
+            digest.verify_against_instance(&mut layouter, config.instance, 0)
+        }
+    }
+
+    /// TaggedCircuit without loading the range table
+    #[derive(Clone, Debug, Default)]
+    struct NoTableCircuit {
+        message: Vec<u8>,
+    }
+
+    impl Circuit<Fr> for NoTableCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            NoTableCircuit { message: vec![0; self.message.len()] }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            RMD160Circuit::<1>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            let digest = rmd160chip.hash_bytes(&mut layouter, &self.message)?;
+            digest.verify_against_instance(&mut layouter, config.instance, 0)
+        }
+    }
+
+    /// chain two blocks with synthesize_sub, the first digest is the iv of the second block
+    #[derive(Clone, Debug, Default)]
+    struct WrapperCircuit {
+        blocks: [[u32; 16]; 2],
+    }
+
+    impl ChipTest for WrapperCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let mut state = helperchip.assign_w(layouter, &H0, 0)?;
+            for block in self.blocks.iter() {
+                let inputs = helperchip.assign_inputs(layouter, block, 0)?;
+                state = rmd160chip.synthesize_sub(layouter, &state, &inputs)?.into_cells();
+            }
+            AssignedDigest::from_cells(state).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_synthesize_sub() {
+        let blocks = [[0x01234567; 16], [0x89abcdef; 16]];
+        let digest = rmd160_block_words(rmd160_block_words(H0, blocks[0]), blocks[1]);
+        let instances = digest.iter().map(|w| Fr::from(*w as u64)).collect();
+        let test_circuit = ChipCircuit(WrapperCircuit { blocks });
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// hash several messages with a single chip instance
+    #[derive(Clone, Debug, Default)]
+    struct ReuseCircuit {
+        messages: Vec<Vec<u8>>,
+    }
+
+    impl ChipTest for ReuseCircuit {
+        fn without_witnesses(&self) -> Self {
+            ReuseCircuit { messages: self.messages.iter().map(|m| vec![0; m.len()]).collect() }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            for (i, message) in self.messages.iter().enumerate() {
+                let digest = rmd160chip.hash_bytes(layouter, message)?;
+                digest.verify_against_instance(layouter, config.instance, i * 5)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_chip_reuse() {
+        let messages = vec![b"abc".to_vec(), vec![0x42; 100], b"abc".to_vec()];
+        let instances = messages.iter()
+            .flat_map(|m| digest_instances(rmd160_digest(m)))
+            .collect::<Vec<_>>();
+        assert_eq!(instances[0..5], instances[10..15]);
+        let test_circuit = ChipCircuit(ReuseCircuit { messages });
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// pack the digest returned by assign_content, which only works if the
+    /// returned cells take part in the copy constraints
+    #[derive(Clone, Debug, Default)]
+    struct ContentCircuit {
+        inputs: [u32; 16],
+    }
+
+    impl ChipTest for ContentCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let inputs = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let digest = rmd160chip.assign_content(layouter, &w, &inputs)?;
+            let (lines_digest, _) = rmd160chip.assign_content_with_lines(layouter, &w, &inputs)?;
+            for i in 0..5 {
+                assert_eq!(cell_to_u32(&digest[i]), cell_to_u32(&lines_digest[i]));
+            }
+            let packed = rmd160chip.digest_packed(layouter, &digest)?;
+            for (i, cell) in packed.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assign_content_output() {
+        let inputs = [0xdeadbeef; 16];
+        let digest = rmd160_block_words(H0, inputs);
+        let low = digest[0..4].iter().rev()
+            .fold(Fr::zero(), |acc, w| acc * Fr::from(1u64 << 32) + Fr::from(*w as u64));
+        let instances = vec![low, Fr::from(digest[4] as u64)];
+        let prover = MockProver::run(K, &ChipCircuit(ContentCircuit { inputs }), vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let wrong = vec![instances[0], instances[1] + Fr::one()];
+        let prover = MockProver::run(K, &ChipCircuit(ContentCircuit { inputs }), vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_missing_range_table() {
+        let message = b"abc".to_vec();
+        let instances = digest_instances(rmd160_digest(&message));
+        let test_circuit = NoTableCircuit { message };
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        let errors = prover.verify().unwrap_err();
+        assert!(format!("{:?}", errors).contains("padding byte range"));
+    }
+
+    #[test]
+    fn test_domain_tag() {
+        let message = b"transfer 10".to_vec();
+        let tags = [b"tx:".to_vec(), b"block:".to_vec()];
+        let digests = tags.clone().map(|tag| rmd160_digest(&[&tag[..], &message[..]].concat()));
+        assert_ne!(digests[0], digests[1]);
+        for (tag, digest) in tags.iter().zip(digests.iter()) {
+            let test_circuit = TaggedCircuit { tag: tag.clone(), message: message.clone() };
+            let prover = MockProver::run(K, &test_circuit, vec![digest_instances(*digest)]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // the digest under the other tag is rejected
+        let test_circuit = TaggedCircuit { tag: tags[0].clone(), message };
+        let prover = MockProver::run(K, &test_circuit, vec![digest_instances(digests[1])]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Fixture {
+        input_hex: String,
+        digest_hex: String,
+    }
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i+2], 16).unwrap())
+            .collect()
+    }
+
+    /// (message, digest) pairs of tests/fixtures/rmd160.json
+    fn load_fixtures() -> Vec<(Vec<u8>, [u8; 20])> {
+        let fixtures: Vec<Fixture> = serde_json::from_str(
+            include_str!("../../tests/fixtures/rmd160.json")
+        ).unwrap();
+        fixtures.into_iter()
+            .map(|f| (from_hex(&f.input_hex), digest_from_hex(&f.digest_hex).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rmd160_fixtures() {
+        for (message, digest) in load_fixtures() {
+            assert_eq!(rmd160_digest(&message), digest, "host digest of {} bytes", message.len());
+            let test_circuit = ChipCircuit(PrepaddedCircuit { blocks: message_blocks(&message) });
+            let prover = MockProver::run(K, &test_circuit, vec![digest_instances(digest)]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "circuit digest of {} bytes", message.len());
+            verify_consistency(&message);
+        }
+    }
+
+    /// hash the blocks and keep the digest words read back from the
+    /// assigned cells, the word at corrupt (block, index) is flipped in
+    /// the witness only
+    #[derive(Clone, Debug, Default)]
+    struct ConsistencyCircuit {
+        blocks: Vec<[u32; 16]>,
+        corrupt: Option<(usize, usize)>,
+        digest: std::cell::RefCell<[u32; 5]>,
+    }
+
+    impl ChipTest for ConsistencyCircuit {
+        fn without_witnesses(&self) -> Self {
+            ConsistencyCircuit { blocks: vec![[0; 16]; self.blocks.len()], ..Self::default() }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let mut blocks = vec![];
+            for (n, block) in self.blocks.iter().enumerate() {
+                let mut block = *block;
+                if let Some((_, index)) = self.corrupt.filter(|(b, _)| *b == n) {
+                    block[index] ^= 1;
+                }
+                blocks.push(helperchip.assign_inputs(layouter, &block, 0)?);
+            }
+            let r = rmd160chip.assign_prepadded(layouter, &w, &blocks)?;
+            *self.digest.borrow_mut() = r.clone().map(|c| cell_to_u32(&c));
+            AssignedDigest::from_cells(r).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    /// compare the digest of the host hasher with the digest words assigned
+    /// by the circuit, Err names every word that differs
+    fn consistency_diff(message: &[u8], corrupt: Option<(usize, usize)>) -> Result<(), String> {
+        let host = rmd160_digest(message);
+        let test_circuit = ChipCircuit(ConsistencyCircuit {
+            blocks: message_blocks(message),
+            corrupt,
+            ..ConsistencyCircuit::default()
+        });
+        let prover = MockProver::run(K, &test_circuit, vec![digest_instances(host)]).unwrap();
+        let circuit = *test_circuit.digest.borrow();
+        let host_words = host.chunks_exact(4)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()));
+        let diff = host_words.zip(circuit.iter()).enumerate()
+            .filter(|(_, (h, c))| h != *c)
+            .map(|(i, (h, c))| format!("word {}: host {:08x} circuit {:08x}", i, h, c))
+            .collect::<Vec<_>>();
+        if !diff.is_empty() {
+            return Err(format!("{} bytes message: {}", message.len(), diff.join(", ")));
+        }
+        prover.verify().map_err(|e| format!("{} bytes message: {:?}", message.len(), e))
+    }
+
+    /// assert that the host and the circuit agree on the digest of message
+    fn verify_consistency(message: &[u8]) {
+        if let Err(diff) = consistency_diff(message, None) {
+            panic!("digest mismatch, {}", diff);
+        }
+    }
+
+    #[test]
+    fn test_verify_consistency() {
+        verify_consistency(b"abc");
+        verify_consistency(&[0x5a; 64]);
+        let diff = consistency_diff(b"abc", Some((0, 0))).unwrap_err();
+        assert!(diff.starts_with("3 bytes message: word "), "{}", diff);
+        assert!(diff.contains("word 0: host f708b28e"), "{}", diff);
+    }
+
+    fn digest_instances(digest: [u8; 20]) -> Vec<Fr> {
+        digest.chunks_exact(4)
+            .map(|w| Fr::from(u32::from_le_bytes(w.try_into().unwrap()) as u64))
+            .collect()
+    }
+
+    #[test]
+    fn test_hash_bytes_lengths() {
+        // 56 and 120 bytes leave no room for the length field, the padding
+        // spills into a second and a third block
+        let cases = [
+            (b"".to_vec(), "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+            (b"abc".to_vec(), "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+            (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec(),
+                "12a053384a9c0c88e405a06c27dcf49ada62eb2b"),
+            ((0..120).collect::<Vec<u8>>(), "b89cdc109009f1982c8b34fca446953584d3f6c4"),
+        ];
+        for (message, hex) in cases {
+            let digest = digest_from_hex(hex).unwrap();
+            assert_eq!(rmd160_digest(&message), digest);
+            let test_circuit = TaggedCircuit { tag: vec![], message: message.clone() };
+            let prover = MockProver::run(K, &test_circuit, vec![digest_instances(digest)]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "{} bytes", message.len());
+
+            let mut wrong = digest;
+            wrong[19] ^= 1;
+            let prover = MockProver::run(K, &test_circuit, vec![digest_instances(wrong)]).unwrap();
+            assert!(prover.verify().is_err(), "{} bytes", message.len());
+        }
+    }
+
+    /// keygen for keys, then create_proof for circuit and verify_proof
+    /// under the keys of keys, with the KZG backend on bn256
+    fn verifies_under_keys_of(keys: &TaggedCircuit, circuit: &TaggedCircuit, instances: &[Fr]) -> bool {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+        let vk = keygen_vk(&params, &keys.without_witnesses()).unwrap();
+        let pk = keygen_pk(&params, vk, &keys.without_witnesses()).unwrap();
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<'_, Bn256>, _, _, _, _>(
+            &params,
+            &pk,
+            &[circuit.clone()],
+            &[&[instances]],
+            &mut rng,
+            &mut transcript,
+        ).unwrap();
+        let proof = transcript.finalize();
+
+        let strategy = SingleStrategy::new(&params);
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        verify_proof::<KZGCommitmentScheme<Bn256>, VerifierGWC<'_, Bn256>, _, _, _>(
+            params.verifier_params(),
+            pk.get_vk(),
+            strategy,
+            &[&[instances]],
+            &mut transcript,
+        ).is_ok()
+    }
+
+    #[test]
+    fn test_hash_bytes_shorter_length() {
+        // the keys are for 3 byte messages, a 2 byte message fills the same
+        // single block and is proven with its own honest digest
+        let keys = TaggedCircuit { tag: vec![], message: b"abc".to_vec() };
+        let instances = digest_instances(rmd160_digest(b"abc"));
+        assert!(verifies_under_keys_of(&keys, &keys, &instances));
+
+        let shorter = TaggedCircuit { tag: vec![], message: b"ab".to_vec() };
+        let instances = digest_instances(rmd160_digest(b"ab"));
+        assert!(!verifies_under_keys_of(&keys, &shorter, &instances));
+    }
+
+    #[test]
+    fn test_rmd160_prepadded() {
+        let mut first = [0u32; 16];
+        let mut second = [0u32; 16];
+        // 64 bytes of 0x61 followed by a padding block with 512 bits of length
+        first.iter_mut().for_each(|w| *w = 0x6161_6161);
+        second[0] = 0x80;
+        second[14] = 512;
+        let digest = hash_prepadded(&[first, second]);
+        assert_eq!(digest, rmd160_digest(&[0x61; 64]));
+        let test_circuit = ChipCircuit(PrepaddedCircuit { blocks: vec![first, second] });
+        let prover = MockProver::run(K, &test_circuit, vec![digest_instances(digest)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct RowsCircuit {
+        inputs: [u32; 16],
+    }
+
+    impl ChipTest for RowsCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            layouter.assign_region(
+                || "estimated rows",
+                |mut region| {
+                    let (_, end) = rmd160chip.assign_block(&mut region, 0, &w, &input)?;
+                    assert_eq!(end, RMD160Chip::<Fr>::estimated_rows(1));
+                    assert_eq!(end, 80 * ROWS_PER_ROUND * 2 + ROWS_PER_COMPRESS);
+                    Ok(())
+                }
+            )
+        }
+    }
+
+    #[test]
+    fn test_estimated_rows() {
+        let test_circuit = ChipCircuit(RowsCircuit { inputs: [0; 16] });
+        let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        assert_eq!(RMD160Chip::<Fr>::estimated_rows(4), 4 * RMD160Chip::<Fr>::estimated_rows(1));
+    }
+
+    #[test]
+    fn test_layout_info() {
+        let info = RMD160Chip::<Fr>::layout_info();
+        let mut cs = ConstraintSystem::<Fr>::default();
+        RMD160Chip::<Fr>::configure(&mut cs);
+        assert_eq!(cs.num_advice_columns(), info.advice_columns);
+        assert_eq!(cs.num_selectors(), info.selectors);
+        // the lookup table columns are fixed columns as well
+        assert_eq!(cs.num_fixed_columns(), info.fixed_columns + info.table_columns);
+        assert_eq!(cs.num_instance_columns(), 0);
+        assert!(info.table_rows < 1 << K);
+        // the round function table alone needs k = 15 for any number of blocks
+        assert_eq!(info.min_k, 15);
+        assert!(info.table_rows > 1 << (info.min_k - 1));
+        assert!(info.table_rows + cs.blinding_factors() + 1 < 1 << info.min_k);
+        assert!(RangeTable::ROWS < info.table_rows);
+    }
+
+    #[cfg(feature = "stats")]
+    #[derive(Clone, Debug, Default)]
+    struct StatsCircuit {
+        inputs: [u32; 16],
+    }
+
+    #[cfg(feature = "stats")]
+    impl ChipTest for StatsCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            layouter.assign_region(
+                || "synthesis stats",
+                |mut region| {
+                    rmd160chip.reset_stats();
+                    rmd160chip.assign_block(&mut region, 0, &w, &input)?;
+                    let stats = rmd160chip.stats();
+                    // one selector per round, one for the compress step and one per
+                    // u32 word row of the digest
+                    assert_eq!(stats.selectors_enabled, 166);
+                    assert_eq!(stats.selector_rows[..2], [160, 1]);
+                    assert!(!rmd160chip.idle_gates().contains(&"compress"));
+                    assert!(rmd160chip.idle_gates().contains(&"hmac key"));
+                    // a..e and x are bound in every round, r0, r1, r2 in compress and
+                    // the digest words on their u32 word rows
+                    assert_eq!(stats.equality_constraints, 160 * 6 + 15 + 5);
+                    Ok(())
+                }
+            )
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_synthesis_stats() {
+        let test_circuit = ChipCircuit(StatsCircuit { inputs: [0; 16] });
+        let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// runs every assign method which enables a selector of its own
+    #[cfg(feature = "stats")]
+    #[derive(Clone, Debug, Default)]
+    struct SelectorAuditCircuit;
+
+    #[cfg(feature = "stats")]
+    impl ChipTest for SelectorAuditCircuit {
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            rmd160chip.reset_stats();
+            assert_eq!(rmd160chip.idle_gates(), RMD160Chip::<Fr>::gate_names());
+
+            // padding, length field, hmac key, rounds and compress
+            let key = vec![helperchip.assign_value(layouter, 0x4a)?];
+            let (_, mac) = rmd160chip.hmac_rmd160(layouter, &key, b"abc")?;
+            // pair constants, conditional swap and digest pack
+            let bit = helperchip.assign_value(layouter, 1)?;
+            let (left, right) = rmd160chip.conditional_swap(layouter, &bit, &mac, &mac)?;
+            let parent = rmd160chip.hash_pair_digests(layouter, &left, &right)?;
+            rmd160chip.digest_packed(layouter, parent.cells())?;
+            // bits pack
+            let bits = layouter.assign_region(
+                || "zero bits",
+                |mut region| {
+                    let mut cells = vec![];
+                    for i in 0..512 {
+                        cells.push(region.assign_advice(|| "bit", config.helperconfig.limb, i, || Value::known(Fr::zero()))?);
+                    }
+                    Ok(cells.try_into().unwrap())
+                }
+            )?;
+            rmd160chip.assign_bits_block(layouter, &bits)?;
+
+            let idle = rmd160chip.idle_gates();
+            assert!(idle.is_empty(), "gates without any enabled row: {:?}", idle);
+            assert!(rmd160chip.stats().selector_rows.iter().all(|rows| *rows > 0));
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_every_gate_fires() {
+        let prover = MockProver::run(K, &ChipCircuit(SelectorAuditCircuit), vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct SharedRegionCircuit {
+        blocks: [[u32; 16]; 2],
+    }
+
+    impl ChipTest for SharedRegionCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let first = helperchip.assign_inputs(layouter, &self.blocks[0], 0)?;
+            let second = helperchip.assign_inputs(layouter, &self.blocks[1], 0)?;
+            let digest = layouter.assign_region(
+                || "two blocks",
+                |mut region| {
+                    let (state, offset) = rmd160chip.assign_block(&mut region, 0, &w, &first)?;
+                    let (digest, end) = rmd160chip.assign_block(&mut region, offset, &state, &second)?;
+                    assert_eq!(end, RMD160Chip::<Fr>::estimated_rows(2));
+                    Ok(digest)
+                }
+            )?;
+            for i in 0..5 {
+                layouter.constrain_instance(digest[i].cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_blocks_in_shared_region() {
+        let blocks = [[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16], [0xdead_beef; 16]];
+        let test_circuit = ChipCircuit(SharedRegionCircuit { blocks });
+        let instances = digest_instances(hash_prepadded(&blocks));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_non_canonical_word() {
+        let mut inputs = [0; 16].map(|x| Fr::from(x as u64));
+        assert!(RMD160Chip::<Fr>::check_words(&inputs).is_ok());
+        inputs[3] = Fr::from(1u64 << 40);
+        assert!(matches!(
+            RMD160Chip::<Fr>::check_words(&inputs),
+            Err(RMD160Error::NonCanonicalWord(3))
+        ));
+        // RawInputCircuit checks the error is NonCanonicalWord(3) for a
+        // message word and NonCanonicalState(3) for a start state word
+        let test_circuit = ChipCircuit(RawInputCircuit { inputs, as_state: false });
+        assert!(MockProver::run(K, &test_circuit, vec![vec![]]).is_err());
+        let test_circuit = ChipCircuit(RawInputCircuit { inputs, as_state: true });
+        assert!(MockProver::run(K, &test_circuit, vec![vec![]]).is_err());
+        assert_eq!(RMD160Error::NonCanonicalState(3).to_string(), "start state word 3 is not a u32");
+
+        // message word 8 has no start state word of the same index
+        inputs[3] = Fr::from(0xffff_ffffu64);
+        inputs[8] = Fr::from(1u64 << 40);
+        let test_circuit = ChipCircuit(RawInputCircuit { inputs, as_state: false });
+        assert!(MockProver::run(K, &test_circuit, vec![vec![]]).is_err());
+        inputs[8] = Fr::zero();
+        let test_circuit = ChipCircuit(RawInputCircuit { inputs, as_state: true });
+        assert!(MockProver::run(K, &test_circuit, vec![vec![]]).is_ok());
+    }
+
+    /// feeds field elements straight into the chip, bypassing the u32 typed
+    /// inputs, either as message words or (the first five) as start state
+    #[derive(Clone, Debug, Default)]
+    struct RawInputCircuit {
+        inputs: [Fr; 16],
+        as_state: bool,
+    }
+
+    impl ChipTest for RawInputCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let raw = helperchip.assign_field_inputs(layouter, &self.inputs, 0)?;
+            let result = if self.as_state {
+                let input = helperchip.assign_inputs(layouter, &[0; 16], 0)?;
+                let state = raw[..5].to_vec().try_into().unwrap();
+                rmd160chip.assign_content(layouter, &state, &input)
+            } else {
+                let w = helperchip.assign_w(layouter, &H0, 0)?;
+                rmd160chip.assign_content(layouter, &w, &raw)
+            };
+            // the index of the first bad input tells a bad start state word
+            // apart from a bad message word
+            if let Err(error) = &result {
+                match (error, RMD160Chip::<Fr>::check_words(&self.inputs)) {
+                    (RMD160Error::NonCanonicalState(i), Err(RMD160Error::NonCanonicalWord(j))) =>
+                        assert!(self.as_state && *i == j, "{}", error),
+                    (RMD160Error::NonCanonicalWord(i), Err(RMD160Error::NonCanonicalWord(j))) =>
+                        assert!(!self.as_state && *i == j, "{}", error),
+                    _ => panic!("{}", error),
+                }
+            }
+            result?;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct ExposeCircuit {
+        inputs: [[u32; 16]; 2],
+    }
+
+    impl ChipTest for ExposeCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            // the words of the two digests are interleaved in the instance column
+            for n in 0..2 {
+                let w = helperchip.assign_w(layouter, &H0, 0)?;
+                let input = helperchip.assign_inputs(layouter, &self.inputs[n], 0)?;
+                rmd160chip.assign_and_expose(
+                    layouter,
+                    &w,
+                    &input,
+                    config.instance,
+                    [0, 2, 4, 6, 8].map(|i| i + n),
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assign_and_expose() {
+        let blocks = [[7; 16], [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16]];
+        let digests = blocks.map(|b| host_digest(&b));
+        let instances = (0..10).map(|i| digests[i % 2][i / 2]).collect::<Vec<_>>();
+        let test_circuit = ChipCircuit(ExposeCircuit { inputs: blocks });
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mut swapped = instances;
+        swapped.swap(0, 1);
+        let prover = MockProver::run(K, &test_circuit, vec![swapped]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn test_rmd160_cost() {
+        use halo2_proofs::dev::CircuitCost;
+        use halo2_proofs::halo2curves::bn256::G1;
+        let (test_circuit, _) = batch_circuit([[0; 16]]);
+        let cost = CircuitCost::<G1, RMD160Circuit<1>>::measure(K as usize, &test_circuit);
+        // the fields are private, Debug is the only way to read them
+        let mut cs = ConstraintSystem::<Fr>::default();
+        RMD160Circuit::<1>::configure(&mut cs);
+        let fields = format!("{:?}", cost);
+        assert!(fields.contains(&format!("k: {},", K)), "{}", fields);
+        assert!(fields.contains(&format!("lookups: {},", cs.lookups().len())), "{}", fields);
+
+        // the marginal size is what every further instance adds to the proof
+        let one: usize = cost.proof_size(1).into();
+        let two: usize = cost.proof_size(2).into();
+        let marginal: usize = cost.marginal_proof_size().into();
+        assert!(marginal > 0);
+        assert_eq!(two - one, marginal);
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn test_rmd160_layout() {
+        use plotters::prelude::*;
+        let (test_circuit, _) = batch_circuit([[0; 16]]);
+        let path = std::env::temp_dir().join("rmd160-layout.png");
+        let root = BitMapBackend::new(&path, (1024, 3096)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root.titled("RMD160 Layout", ("sans-serif", 60)).unwrap();
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(K, &test_circuit, &root)
+            .unwrap();
+    }
+
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn test_render_layout_svg() {
+        let (test_circuit, _) = batch_circuit([[0; 16]]);
+        let path = std::env::temp_dir().join("rmd160-layout.svg");
+        super::render_layout_svg(K, &test_circuit, 0..4 * ROWS_PER_ROUND, &path).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.starts_with("<svg"), "{}", &svg[..svg.len().min(80)]);
+    }
+
+    fn rmd160_words_bytes(cells: &[AssignedCell<Fr, Fr>; 5]) -> [u8; 20] {
+        let mut bytes = [0; 20];
+        for i in 0..5 {
+            bytes[i*4..i*4+4].copy_from_slice(&cell_to_u32(&cells[i]).to_le_bytes());
+        }
+        bytes
+    }
+
+    fn host_digest(words: &[u32; 16]) -> Vec<Fr> {
+        compress(&H0.to_vec(), words.to_vec())
+            .into_iter()
+            .map(|x| Fr::from(x as u64))
+            .collect()
+    }
+
+    fn batch_circuit<const N: usize>(blocks: [[u32; 16]; N]) -> (RMD160Circuit<N>, Vec<Fr>) {
+        let instances = blocks.iter().flat_map(host_digest).collect();
+        (RMD160Circuit { inputs: blocks }, instances)
+    }
+
+    #[test]
+    fn test_rmd160_circuit() {
+        let (test_circuit, instances) = batch_circuit([[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16]]);
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the padded block of "abc" against the digest of the reference
+        let block = pad_message(b"abc");
+        let words = block.chunks_exact(4)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let (test_circuit, instances) = batch_circuit([words]);
+        assert_eq!(compress_block(H0, block[..].try_into().unwrap()), digest_bytes_to_words(rmd160_digest(b"abc")));
+        assert_eq!(instances, digest_instances(rmd160_digest(b"abc")));
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// random (start state, block) pairs hashed by assign_content, the
+    /// digests are exposed at the instance column
+    #[derive(Clone, Debug, Default)]
+    struct PropertyCircuit {
+        cases: Vec<([u32; 5], [u32; 16])>,
+    }
+
+    impl ChipTest for PropertyCircuit {
+        fn without_witnesses(&self) -> Self {
+            PropertyCircuit { cases: vec![([0; 5], [0; 16]); self.cases.len()] }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            for (n, (state, block)) in self.cases.iter().enumerate() {
+                let w = helperchip.assign_w(layouter, state, 0)?;
+                let input = helperchip.assign_inputs(layouter, block, 0)?;
+                let r = rmd160chip.assign_content(layouter, &w, &input)?;
+                AssignedDigest::from_cells(r).verify_against_instance(layouter, config.instance, n * 5)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// a word biased towards the carry edges: near 0, near 2^31 and near
+    /// u32::MAX, so the sums of the round and compress gates overflow
+    fn edge_word(rng: &mut impl rand::Rng) -> u32 {
+        let delta = rng.gen_range(0..4);
+        match rng.gen_range(0..5) {
+            0 => u32::MAX - delta,
+            1 => delta,
+            2 => (1 << 31) - 2 + delta,
+            3 => u32::MAX,
+            _ => rng.gen(),
+        }
+    }
+
+    #[test]
+    fn test_assign_content_random_blocks() {
+        use rand::{Rng, SeedableRng};
+        // blocks per MockProver run, 16 blocks take about 44k rows
+        const BATCH: usize = 16;
+        const RUNS: usize = 16;
+        let seed = std::env::var("RMD160_PROPTEST_SEED").ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0x5eed);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        for run in 0..RUNS {
+            let cases = (0..BATCH).map(|n| {
+                // every batch starts with the all ones state and block
+                if n == 0 {
+                    return ([u32::MAX; 5], [u32::MAX; 16]);
+                }
+                let edges = rng.gen_bool(0.5);
+                let word = |rng: &mut rand::rngs::StdRng| if edges { edge_word(rng) } else { rng.gen() };
+                let state = std::array::from_fn(|_| word(&mut rng));
+                let block = std::array::from_fn(|_| word(&mut rng));
+                (state, block)
+            }).collect::<Vec<_>>();
+            let instances = cases.iter()
+                .flat_map(|(state, block)| rmd160_block_words(*state, *block))
+                .map(|w| Fr::from(w as u64))
+                .collect::<Vec<_>>();
+            let test_circuit = ChipCircuit(PropertyCircuit { cases: cases.clone() });
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "seed {} run {}: {:x?}", seed, run, cases);
+        }
+    }
+
+    #[test]
+    fn test_verify_against_instance() {
+        let (test_circuit, mut instances) = batch_circuit([[5; 16], [6; 16]]);
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        instances[7] += Fr::one();
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// packs the digest of inputs, or with shifted the digest with w0 raised
+    /// by 2^32 and w1 lowered by one, which packs to the same low
+    #[derive(Clone, Debug, Default)]
+    struct PackCircuit {
+        inputs: [u32; 16],
+        shifted: bool,
+    }
+
+    impl ChipTest for PackCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let mut digest = rmd160chip.assign_content(layouter, &w, &input)?;
+            if self.shifted {
+                let mut words = digest.clone().map(|cell| cell_to_value(&cell));
+                words[0] += Fr::from(1u64 << 32);
+                words[1] -= Fr::one();
+                digest = layouter.assign_region(
+                    || "shifted digest",
+                    |mut region| {
+                        let cells = words.iter().enumerate()
+                            .map(|(i, word)| region.assign_advice(
+                                || "shifted word",
+                                config.helperconfig.limb,
+                                i,
+                                || Value::known(*word),
+                            ))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        Ok(cells.try_into().unwrap())
+                    }
+                )?;
+            }
+            let packed = rmd160chip.digest_packed(layouter, &digest)?;
+            for i in 0..2 {
+                layouter.constrain_instance(packed[i].cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_digest_packed() {
+        let inputs = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16];
+        let words = compress(&H0.to_vec(), inputs.to_vec());
+        let bytes = words.iter().flat_map(|w| w.to_le_bytes()).collect::<Vec<u8>>();
+        let low = u128::from_le_bytes(bytes[0..16].try_into().unwrap());
+        let high = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        assert_eq!(high, words[4]);
+        let instances = vec![Fr::from_u128(low), Fr::from(high as u64)];
+        let test_circuit = ChipCircuit(PackCircuit { inputs, shifted: false });
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let wrong = vec![Fr::from_u128(low ^ 1), Fr::from(high as u64)];
+        let prover = MockProver::run(K, &test_circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // w0 off by 2^32 packs to the honest low, only its u32 word row fails
+        assert!(words[1] > 0);
+        let test_circuit = ChipCircuit(PackCircuit { inputs, shifted: true });
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        let errors = prover.verify().unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("u32 word")), "{:?}", errors);
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct LinesCircuit {
+        inputs: [u32; 16],
+    }
+
+    impl ChipTest for LinesCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let (_, lines) = rmd160chip.assign_content_with_lines(layouter, &w, &input)?;
+            // left line words followed by the parallel line words
+            for (i, cell) in lines.iter().flatten().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_line_outputs() {
+        // the lines borrow the start state and index the inputs in place
+        for inputs in [
+            [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16],
+            [0xffff_ffff; 16],
+            [0, 0x8000_0000, 0x7fff_ffff, 0xdeadbeef, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+        ] {
+            let (left, right) = compress_lines(&H0.to_vec(), &inputs.to_vec());
+            let instances = left.into_iter().chain(right)
+                .map(|x| Fr::from(x as u64))
+                .collect::<Vec<_>>();
+            let test_circuit = ChipCircuit(LinesCircuit { inputs });
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+
+    /// recompute the digest from the debug lines in a standalone compress
+    #[derive(Clone, Debug, Default)]
+    struct DebugLinesCircuit {
+        inputs: [u32; 16],
+    }
+
+    impl ChipTest for DebugLinesCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let (r1, r2, digest) = rmd160chip.assign_content_debug(layouter, &w, &input)?;
+            let recomputed = layouter.assign_region(
+                || "standalone compress",
+                |mut region| rmd160chip.assign_compress(&mut region, 0, &w, &r1, &r2)
+            )?;
+            for i in 0..5 {
+                assert_eq!(cell_to_u32(&recomputed[i]), cell_to_u32(&digest[i]));
+            }
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)?;
+            AssignedDigest::from_cells(recomputed).verify_against_instance(layouter, config.instance, 5)
+        }
+    }
+
+    #[test]
+    fn test_debug_lines() {
+        let inputs = [0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let digest = host_digest(&inputs);
+        let instances = digest.iter().chain(digest.iter()).cloned().collect();
+        let prover = MockProver::run(K, &ChipCircuit(DebugLinesCircuit { inputs }), vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// a compress step whose output word anew is raised by one after the
+    /// honest assignment, the instance expects the raised word
+    #[derive(Clone, Debug, Default)]
+    struct CompressTamperCircuit {
+        inputs: [u32; 16],
+        tamper: bool,
+    }
+
+    impl ChipTest for CompressTamperCircuit {
+        fn without_witnesses(&self) -> Self {
+            CompressTamperCircuit { inputs: [0; 16], tamper: self.tamper }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let (r1, r2, _) = rmd160chip.assign_content_debug(layouter, &w, &input)?;
+            let digest = layouter.assign_region(
+                || "tampered compress",
+                |mut region| {
+                    let mut digest = rmd160chip.assign_compress(&mut region, 0, &w, &r1, &r2)?;
+                    if self.tamper {
+                        let anew = cell_to_value(&digest[4]) + Fr::one();
+                        digest[4] = rmd160chip.assign_cell(&mut region, 0, CompressGate::anew(), anew)?;
+                    }
+                    Ok(digest)
+                }
+            )?;
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_compress_gate() {
+        let inputs = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16];
+        let instances = host_digest(&inputs);
+        let test_circuit = ChipCircuit(CompressTamperCircuit { inputs, tamper: false });
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mut raised = instances;
+        raised[4] += Fr::one();
+        let test_circuit = ChipCircuit(CompressTamperCircuit { inputs, tamper: true });
+        let prover = MockProver::run(K, &test_circuit, vec![raised]).unwrap();
+        let errors = prover.verify().unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("compress")), "{:?}", errors);
+    }
+
+    /// a compress step whose first digest word with a carry is reassigned
+    /// with the carry lowered by one and the word raised by 2^32, which
+    /// still satisfies the compress gate and the copy to its u32 word row
+    #[derive(Clone, Debug, Default)]
+    struct CarryTamperCircuit {
+        inputs: [u32; 16],
+        tamper: bool,
+    }
+
+    impl ChipTest for CarryTamperCircuit {
+        fn without_witnesses(&self) -> Self {
+            CarryTamperCircuit { inputs: [0; 16], tamper: self.tamper }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let (r1, r2, _) = rmd160chip.assign_content_debug(layouter, &w, &input)?;
+            let digest = layouter.assign_region(
+                || "tampered carry",
+                |mut region| {
+                    let (mut digest, carries) =
+                        rmd160chip.assign_compress_with_carries(&mut region, 0, &w, &r1, &r2)?;
+                    if self.tamper {
+                        let i = (0..5).find(|i| cell_to_u32(&carries[*i]) > 0).unwrap();
+                        // digest word i is the output of lane i + 1
+                        let [_, _, _, _, ca_cell, new_cell] = CompressGate::lane((i + 1) % 5);
+                        let ca = cell_to_value(&carries[i]) - Fr::one();
+                        let new = cell_to_value(&digest[i]) + Fr::from(1u64 << 32);
+                        rmd160chip.assign_cell(&mut region, 0, ca_cell, ca)?;
+                        digest[i] = rmd160chip.assign_cell(&mut region, 0, new_cell, new)?;
+                        rmd160chip.assign_cell(&mut region, 5 + i, WordGate::w(), new)?;
+                    }
+                    Ok(digest)
+                }
+            )?;
+            // the tampered digest is left unexposed, the u32 word rows alone
+            // must reject it
+            if self.tamper {
+                return Ok(());
+            }
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_compress_carry_range() {
+        let inputs = [0xffff_ffff; 16];
+        let instances = host_digest(&inputs);
+        let test_circuit = ChipCircuit(CarryTamperCircuit { inputs, tamper: false });
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let test_circuit = ChipCircuit(CarryTamperCircuit { inputs, tamper: true });
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        let errors = prover.verify().unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("u32 word")), "{:?}", errors);
+    }
+
+    /// the combination rule of Rmd160Spec recomputed on the assigned cells
+    #[derive(Clone, Debug, Default)]
+    struct CombineCircuit {
+        inputs: [u32; 16],
+    }
+
+    impl ChipTest for CombineCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let (r1, r2, _) = rmd160chip.assign_content_debug(layouter, &w, &input)?;
+            let digest = layouter.assign_region(
+                || "compress",
+                |mut region| rmd160chip.assign_compress(&mut region, 0, &w, &r1, &r2)
+            )?;
+            let words = |cells: &[AssignedCell<Fr, Fr>; 5]| cells.clone().map(|c| cell_to_u32(&c));
+            let combined = Rmd160Spec::combine(&words(&w), &words(&r1), &words(&r2));
+            assert_eq!(combined, words(&digest).to_vec());
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_spec_combination() {
+        let inputs = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16];
+        let [left, right] = spec_lines::<Rmd160Spec>(H0, inputs);
+        assert_eq!(Rmd160Spec::COMBINATION, Combination::Cross);
+        assert_eq!(Rmd160Spec::combine(&H0, &left, &right), rmd160_block_words(H0, inputs).to_vec());
+
+        let instances = host_digest(&inputs);
+        let prover = MockProver::run(K, &ChipCircuit(CombineCircuit { inputs }), vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct CarriesCircuit {
+        inputs: [u32; 16],
+        compress: std::cell::RefCell<[u32; 5]>,
+    }
+
+    impl ChipTest for CarriesCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            let (digest, carries) = rmd160chip.assign_content_with_carries(layouter, &w, &input)?;
+            assert_eq!(carries.wc.len(), 160);
+            assert_eq!(carries.w2c.len(), 160);
+            assert!(carries.wc.iter().all(|c| cell_to_u32(c) <= 3));
+            assert!(carries.w2c.iter().all(|c| cell_to_u32(c) <= 1));
+            assert!(carries.compress.iter().all(|c| cell_to_u32(c) <= 2));
+            // every lane keeps its carry in its own cell
+            for i in 0..5 {
+                for j in 0..i {
+                    assert_ne!(carries.compress[i].cell(), carries.compress[j].cell());
+                }
+            }
+            *self.compress.borrow_mut() = carries.compress.clone().map(|c| cell_to_u32(&c));
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_assign_content_carries() {
+        for inputs in [[0xffffffff; 16], [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16]] {
+            let instances = host_digest(&inputs);
+            let test_circuit = ChipCircuit(CarriesCircuit { inputs, ..CarriesCircuit::default() });
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_compress_lane_carries() {
+        // the lane of c + d1 + e2 carries while the lane of a + b1 + c2 does
+        // not, a carry stored in the cell of another lane breaks the gate
+        let inputs = [0xdeadbeef; 16];
+        let test_circuit = ChipCircuit(CarriesCircuit { inputs, ..CarriesCircuit::default() });
+        let prover = MockProver::run(K, &test_circuit, vec![host_digest(&inputs)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        // in digest order: the lanes of b, c, d, e, a
+        assert_eq!(*test_circuit.compress.borrow(), [1, 1, 0, 1, 0]);
+    }
+
+    /// the first step of round `round` of the left or the parallel (pround)
+    /// line from H0 whose cells (col, row, value) are overwritten after the
+    /// honest assignment
+    #[derive(Clone, Debug, Default)]
+    struct RoundTamperCircuit<const RANGE_CHECK: bool> {
+        input: u32,
+        round: usize,
+        pround: bool,
+        tampered: Vec<(usize, usize, Fr)>,
+    }
+
+    impl<const RANGE_CHECK: bool> Circuit<Fr> for RoundTamperCircuit<RANGE_CHECK> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            test_config(meta, RMD160ConfigBuilder::new().enable_range_check(RANGE_CHECK))
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            rmd160chip.load_range_table(&mut layouter)?;
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(&mut layouter, &H0, 0)?;
+            let input = helperchip.assign_value(&mut layouter, self.input as u64)?;
+            layouter.assign_region(
+                || "tampered round",
+                |mut region| {
+                    let witness = step_witness::<Fr, Rmd160Spec>(&H0, self.input, self.round, 0, self.pround);
+                    let _ = rmd160chip.assign_next::<Rmd160Spec>(&mut region, 0, &w, &input, self.round, 0, self.pround, &witness)?;
+                    for (col, row, value) in self.tampered.iter() {
+                        rmd160chip.assign_cell(&mut region, 0, GateCell::adv(*col, *row, "tampered"), *value)?;
+                    }
+                    Ok(())
+                }
+            )
+        }
+    }
+
+    fn tampered_round(tampered: Vec<(usize, usize, u64)>) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        tampered_round_fr(field_values(tampered))
+    }
+
+    fn tampered_round_fr(tampered: Vec<(usize, usize, Fr)>) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let test_circuit = RoundTamperCircuit::<true> { input: 0x12345678, tampered, ..Default::default() };
+        MockProver::run(K, &test_circuit, vec![vec![]]).unwrap().verify()
+    }
+
+    fn field_values(cells: Vec<(usize, usize, u64)>) -> Vec<(usize, usize, Fr)> {
+        cells.into_iter().map(|(col, row, value)| (col, row, Fr::from(value))).collect()
+    }
+
+    #[test]
+    fn test_w1_split_range() {
+        let shift = R[0][0];
+        let w = get_witnesses::<Fr, Rmd160Spec>(0, &H0, 0x12345678, shift, ROUNDS_OFFSET[0], false);
+        assert!(w.w1_h > 0);
+        // move one bit from w1_h across the split into w1_l: w0 is unchanged
+        // while the rotation yields w1 + 2^32 - 1, which is carried along
+        // into w2b and a_next, so every gate equation still holds
+        let w1_l = w.w1_l + (1 << (32 - shift));
+        let w1_h = w.w1_h - 1;
+        let mut ls_limbs = u32_to_limbs::<Fr>(w.w1_l << shift);
+        ls_limbs[3] += Fr::from(256);
+        let wrap = Fr::from((1u64 << 32) - 1);
+        let mut tampered = vec![
+            (RoundGate::w1_l(), Fr::from(w1_l as u64)),
+            (RoundGate::w1_h(), Fr::from(w1_h as u64)),
+            (RoundGate::w1(), Fr::from(w.w1 as u64) + wrap),
+            (RoundGate::w2b(), w.w2b + wrap),
+            (RoundGate::a_next(), Fr::from(w.a_next as u64) + wrap),
+        ];
+        for (limb, limbs) in [
+            (RoundGate::w1_l_limb as fn(usize) -> GateCell, u32_to_limbs::<Fr>(w1_l)),
+            (RoundGate::w1_l_shl_limb, ls_limbs),
+            (RoundGate::w1_h_limb, u32_to_limbs::<Fr>(w1_h)),
+            (RoundGate::w1_h_shl_limb, u32_to_limbs::<Fr>(w1_h << (32 - shift))),
+        ] {
+            for i in 0..4 {
+                tampered.push((limb(i), limbs[i]));
+            }
+        }
+        let tampered = tampered.into_iter()
+            .map(|(gate_cell, value)| (gate_cell.cell[1], gate_cell.cell[2], value))
+            .collect();
+        let errors = tampered_round_fr(tampered).unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("w1 split limb range")), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_w4_split_range() {
+        let w = get_witnesses::<Fr, Rmd160Spec>(0, &H0, 0x12345678, R[0][0], ROUNDS_OFFSET[0], false);
+        assert!(w.w4_h > 0);
+        // move one bit from w4_h across the 22/10 boundary into w4_l: c is
+        // unchanged and c_next becomes c_next + 2^32 - 1
+        let w4_l = w.w4_l + (1 << 22);
+        let w4_h = w.w4_h - 1;
+        let mut ms_limbs = u32_to_limbs::<Fr>(w.w4_l << 10);
+        ms_limbs[3] += Fr::from(256);
+        let mut tampered = vec![
+            (RoundGate::w4_l(), Fr::from(w4_l as u64)),
+            (RoundGate::w4_h(), Fr::from(w4_h as u64)),
+            (RoundGate::c_next(), Fr::from(w.c_next as u64) + Fr::from((1u64 << 32) - 1)),
+        ];
+        for (limb, limbs) in [
+            (RoundGate::w4_l_shl_limb as fn(usize) -> GateCell, ms_limbs),
+            (RoundGate::w4_h_limb, u32_to_limbs::<Fr>(w4_h)),
+            (RoundGate::w4_h_shl_limb, u32_to_limbs::<Fr>(w4_h << 22)),
+        ] {
+            for i in 0..4 {
+                tampered.push((limb(i), limbs[i]));
+            }
+        }
+        let tampered = tampered.into_iter()
+            .map(|(gate_cell, value)| (gate_cell.cell[1], gate_cell.cell[2], value))
+            .collect();
+        let errors = tampered_round_fr(tampered).unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("w4 split limb range")), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_round_function_gate() {
+        let shift = R[0][0];
+        let w = get_witnesses::<Fr, Rmd160Spec>(0, &H0, 0x12345678, shift, ROUNDS_OFFSET[0], false);
+        assert_eq!(w.r & 1, 1);
+        // clear the lowest bit of r and carry the change through the sums and
+        // the rotation, only r = f(b, c, d) is left violated
+        let r = w.r - 1;
+        let w1_l = w.w1_l - 1;
+        let moved = Fr::from(1u64 << shift);
+        let mut tampered = vec![
+            (RoundGate::wb(), w.wb - Fr::one()),
+            (RoundGate::w0(), Fr::from(w.w0 as u64 - 1)),
+            (RoundGate::w1_l(), Fr::from(w1_l as u64)),
+            (RoundGate::w1(), Fr::from(w.w1 as u64) - moved),
+            (RoundGate::w2b(), w.w2b - moved),
+            (RoundGate::a_next(), Fr::from(w.a_next as u64) - moved),
+            (RoundGate::rnibble(0), Fr::from((r & 0xf) as u64)),
+        ];
+        for (limb, limbs) in [
+            (RoundGate::rlimb as fn(usize) -> GateCell, u32_to_limbs::<Fr>(r)),
+            (RoundGate::w1_l_limb, u32_to_limbs::<Fr>(w1_l)),
+            (RoundGate::w1_l_shl_limb, u32_to_limbs::<Fr>(w1_l << shift)),
+        ] {
+            for i in 0..4 {
+                tampered.push((limb(i), limbs[i]));
+            }
+        }
+        let tampered = tampered.into_iter()
+            .map(|(gate_cell, value)| (gate_cell.cell[1], gate_cell.cell[2], value))
+            .collect();
+        let errors = tampered_round_fr(tampered).unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("round function")), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_round_function_families() {
+        // every function of both lines: f, g, h, i, j on the left and j, i, h, g, f on the parallel line
+        for pround in [false, true] {
+            for round in 0..5 {
+                let honest = RoundTamperCircuit::<true> { input: 0x12345678, round, pround, tampered: vec![] };
+                let prover = MockProver::run(K, &honest, vec![vec![]]).unwrap();
+                assert_eq!(prover.verify(), Ok(()), "round {} pround {}", round, pround);
+
+                // flip the lowest bit of r in its nibble and its limb
+                let (shift, offset) = if pround { (PR[round][0], PROUNDS_OFFSET[round]) } else { (R[round][0], ROUNDS_OFFSET[round]) };
+                let w = get_witnesses::<Fr, Rmd160Spec>(round, &H0, 0x12345678, shift, offset, pround);
+                let nibble = RoundGate::rnibble(0).cell;
+                let limb = RoundGate::rlimb(0).cell;
+                let tampered = vec![
+                    (nibble[1], nibble[2], Fr::from(((w.r ^ 1) & 0xf) as u64)),
+                    (limb[1], limb[2], Fr::from(((w.r ^ 1) & 0xff) as u64)),
+                ];
+                let corrupted = RoundTamperCircuit::<true> { input: 0x12345678, round, pround, tampered };
+                let errors = MockProver::run(K, &corrupted, vec![vec![]]).unwrap().verify().unwrap_err();
+                assert!(errors.iter().any(|e| format!("{:?}", e).contains("round function")),
+                    "round {} pround {}: {:?}", round, pround, errors);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wc_range() {
+        let w = get_witnesses::<Fr, Rmd160Spec>(0, &H0, 0x12345678, R[0][0], ROUNDS_OFFSET[0], false);
+        assert!(w.wc.get() <= Carry::MAX_WC);
+        // a carry of 4 with wb raised by 4 * 2^32 keeps w0 + wc * 2^32 == wb
+        let wc = RoundGate::wc().cell;
+        let wb = RoundGate::wb().cell;
+        let tampered = vec![
+            (wc[1], wc[2], Fr::from(w.wc.get() + 4)),
+            (wb[1], wb[2], w.wb + Fr::from(4u64 << 32)),
+        ];
+        let errors = tampered_round_fr(tampered).unwrap_err();
+        assert!(errors.iter().all(|e| format!("{:?}", e).contains("sum with bound")), "{:?}", errors);
+        assert!(tampered_round(vec![(wc[1], wc[2], w.wc.get() + 1)]).is_err());
+    }
+
+    /// the r limbs of test_r_limb_range, in range of the gates but not of the lookups,
+    /// the nibbles of the first two limbs follow so that they still sum to the limbs
+    fn out_of_range_limbs() -> Vec<(usize, usize, u64)> {
+        let limbs = [(0x01 + 256), 0x22, 0x45, 0x67];
+        let nibbles = [(RoundGate::rnibble(1), 0x10), (RoundGate::rnibble(2), 0x2)];
+        (0..4).map(|i| (RoundGate::rlimb(i), limbs[i]))
+            .chain(nibbles)
+            .map(|(gate_cell, value)| (gate_cell.cell[1], gate_cell.cell[2], value))
+            .collect()
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct RangeCheckCircuit<const RANGE_CHECK: bool> {
+        inputs: [u32; 16],
+    }
+
+    impl<const RANGE_CHECK: bool> Circuit<Fr> for RangeCheckCircuit<RANGE_CHECK> {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            test_config(meta, RMD160ConfigBuilder::new().enable_range_check(RANGE_CHECK))
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            assert_eq!(config.rmd160config.range_check_enabled(), RANGE_CHECK);
+            if RANGE_CHECK {
+                rmd160chip.load_range_table(&mut layouter)?;
+            }
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(&mut layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(&mut layouter, &self.inputs, 0)?;
+            let digest = rmd160chip.assign_content(&mut layouter, &w, &input)?;
+            AssignedDigest::from_cells(digest).verify_against_instance(&mut layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_enable_range_check() {
+        let inputs = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16];
+        let instances = host_digest(&inputs);
+        let prover = MockProver::run(K, &RangeCheckCircuit::<false> { inputs }, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(K, &RangeCheckCircuit::<true> { inputs }, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the out of range limbs only get through without the lookups
+        let tampered = RoundTamperCircuit::<false> { input: 0x12345678, tampered: field_values(out_of_range_limbs()), ..Default::default() };
+        let prover = MockProver::run(K, &tampered, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let tampered = RoundTamperCircuit::<true> { input: 0x12345678, tampered: field_values(out_of_range_limbs()), ..Default::default() };
+        let prover = MockProver::run(K, &tampered, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_bcd_limb_range() {
+        // b, c, d = 0xefcdab89, 0x98badcfe, 0x10325476 in the first round from H0
+        for (limb, value, name) in [
+            (RoundGate::blimb as fn(usize) -> GateCell, 0xefcdab89u32, "b limb range"),
+            (RoundGate::climb, 0x98badcfe, "c limb range"),
+            (RoundGate::dlimb, 0x10325476, "d limb range"),
+        ] {
+            // move 256 from the second limb into the first one, the sum is unchanged
+            let bytes = value.to_le_bytes();
+            let limbs = [bytes[0] as u64 + 256, bytes[1] as u64 - 1, bytes[2] as u64, bytes[3] as u64];
+            let tampered = (0..4).map(|i| {
+                let cell = limb(i).cell;
+                (cell[1], cell[2], limbs[i])
+            }).collect::<Vec<_>>();
+            let errors = tampered_round(tampered).unwrap_err();
+            assert!(format!("{:?}", errors).contains(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_r_limb_range() {
+        assert_eq!(tampered_round(vec![]), Ok(()));
+        // r = b ^ c ^ d = 0x67452301 in the first round from H0, move 256
+        // from the second limb into the first one, the limbs still sum to r
+        let errors = tampered_round(out_of_range_limbs()).unwrap_err();
+        assert!(format!("{:?}", errors).contains("r limb range"));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct AssertionsCircuit {
+        inputs: [u32; 16],
+        assertions: bool,
+    }
+
+    impl Circuit<Fr> for AssertionsCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            AssertionsCircuit { inputs: [0; 16], assertions: self.assertions }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            RMD160Circuit::<1>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config)
+                .with_witness_assertions(self.assertions);
+            rmd160chip.load_range_table(&mut layouter)?;
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(&mut layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(&mut layouter, &self.inputs, 0)?;
+            let digest = rmd160chip.assign_content(&mut layouter, &w, &input)?;
+            AssignedDigest::from_cells(digest).verify_against_instance(&mut layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_witness_assertions_toggle() {
+        let inputs = [0xffff_ffff; 16];
+        for assertions in [true, false] {
+            let test_circuit = AssertionsCircuit { inputs, assertions };
+            let prover = MockProver::run(K, &test_circuit, vec![host_digest(&inputs)]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct HashWordsCircuit {
+        state: [u32; 5],
+        words: [u32; 16],
+    }
+
+    impl ChipTest for HashWordsCircuit {
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &self.state, 0)?;
+            let digest = rmd160chip.hash_words(layouter, &w, &self.words)?;
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_hash_words() {
+        let state = [0x0123_4567, 0x89ab_cdef, 0xfedc_ba98, 0x7654_3210, 0xf0e1_d2c3];
+        let words = [0x6162_6364; 16];
+        let expected = rmd160_block_words(state, words);
+        assert_eq!(rmd160_block_words(H0, words).to_vec(), compress(&H0.to_vec(), words.to_vec()));
+        let test_circuit = ChipCircuit(HashWordsCircuit { state, words });
+        let instances = expected.map(|x| Fr::from(x as u64)).to_vec();
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct VerifyDigestCircuit {
+        inputs: [u32; 16],
+        expected: [u32; 5],
+    }
+
+    impl ChipTest for VerifyDigestCircuit {
+        fn without_witnesses(&self) -> Self {
+            VerifyDigestCircuit { inputs: [0; 16], expected: self.expected }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(layouter, &self.inputs, 0)?;
+            rmd160chip.verify_digest(layouter, &w, &input, self.expected)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_verify_digest() {
+        let inputs = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let expected: [u32; 5] = compress(&H0.to_vec(), inputs.to_vec()).try_into().unwrap();
+        let test_circuit = ChipCircuit(VerifyDigestCircuit { inputs, expected });
+        let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        for i in 0..5 {
+            let mut wrong = expected;
+            wrong[i] ^= 1;
+            let test_circuit = ChipCircuit(VerifyDigestCircuit { inputs, expected: wrong });
+            let prover = MockProver::run(K, &test_circuit, vec![vec![]]).unwrap();
+            assert!(prover.verify().is_err(), "word {}", i);
+        }
+    }
+
+    /// one block hashed from its bits and from its words, the digests of
+    /// both are constrained equal and the first is exposed
+    #[derive(Clone, Debug, Default)]
+    struct BitsCircuit {
+        words: [u32; 16],
+        bits: Vec<u64>,
+    }
+
+    impl BitsCircuit {
+        fn new(words: [u32; 16]) -> Self {
+            let bits = words.iter().flat_map(|w| (0..32).map(move |i| ((w >> i) & 1) as u64)).collect();
+            BitsCircuit { words, bits }
+        }
+    }
+
+    impl ChipTest for BitsCircuit {
+        fn without_witnesses(&self) -> Self {
+            BitsCircuit::new([0; 16])
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let bits: [AssignedCell<Fr, Fr>; 512] = layouter.assign_region(
+                || "bits",
+                |mut region| {
+                    let mut cells = vec![];
+                    for (i, bit) in self.bits.iter().enumerate() {
+                        cells.push(region.assign_advice(
+                            || "bit",
+                            config.helperconfig.limb,
+                            i,
+                            || Value::known(Fr::from(*bit)),
+                        )?);
+                    }
+                    Ok(cells.try_into().unwrap())
+                }
+            )?;
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let from_bits = rmd160chip.assign_content_from_bits(layouter, &w, &bits)?;
+            let from_words = rmd160chip.hash_words(layouter, &w, &self.words)?;
+            layouter.assign_region(
+                || "same digest",
+                |mut region| {
+                    for (a, b) in from_bits.iter().zip(from_words.iter()) {
+                        region.constrain_equal(a.cell(), b.cell())?;
+                    }
+                    Ok(())
+                }
+            )?;
+            AssignedDigest::from_cells(from_bits).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_assign_content_from_bits() {
+        for words in [[0x6162_6364; 16], [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0xffff_ffff]] {
+            let test_circuit = ChipCircuit(BitsCircuit::new(words));
+            let prover = MockProver::run(K, &test_circuit, vec![host_digest(&words)]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // a bit of 2 still packs into the right word but is not boolean
+        let mut words = [0; 16];
+        words[0] = 2;
+        let mut test_circuit = ChipCircuit(BitsCircuit::new([0; 16]));
+        test_circuit.words = words;
+        test_circuit.bits[0] = 2;
+        let prover = MockProver::run(K, &test_circuit, vec![host_digest(&words)]).unwrap();
+        let errors = prover.verify().unwrap_err();
+        assert!(errors.iter().any(|e| format!("{:?}", e).contains("\"bits pack\"")), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_rmd160_batch_circuit() {
+        let (test_circuit, instances) = batch_circuit([
+            [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16],
+            [0; 16],
+            [0xffff_ffff; 16],
+        ]);
+        assert_eq!(instances.len(), 15);
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    fn field_guard<F: halo2_proofs::arithmetic::FieldExt>() -> bool {
+        RMD160Chip::<F>::check_field().is_ok()
+    }
+
+    #[test]
+    fn test_field_width_guard() {
+        assert!(field_guard::<Fr>());
+        assert!(field_guard::<halo2_proofs::halo2curves::pasta::Fp>());
+        // a hypothetical 32 bit field can not hold the four way sum
+        assert!(matches!(check_field_bits(32), Err(RMD160Error::FieldTooSmall(32))));
+        assert!(check_field_bits(MIN_FIELD_BITS - 1).is_err());
+        assert!(check_field_bits(MIN_FIELD_BITS).is_ok());
+    }
+
+    fn build_chip<F: RMD160Field>() -> bool {
+        let mut cs = ConstraintSystem::<F>::default();
+        let config = RMD160Chip::<F>::configure(&mut cs);
+        RMD160Chip::<F>::new(config);
+        RMD160Chip::<F>::check_field().is_ok()
+    }
+
+    #[test]
+    fn test_field_trait_multi_curve() {
+        assert!(build_chip::<Fr>());
+        assert!(build_chip::<halo2_proofs::halo2curves::pasta::Fp>());
+        assert!(build_chip::<halo2_proofs::halo2curves::pasta::Fq>());
+    }
+
+    fn configure_holds<F: RMD160Field>() -> bool {
+        let mut cs = ConstraintSystem::<F>::default();
+        RMD160Chip::<F>::configure(&mut cs);
+        F::NUM_BITS > 33
+    }
+
+    #[test]
+    fn test_configure_field_assertion() {
+        assert!(configure_holds::<Fr>());
+        assert!(configure_holds::<halo2_proofs::halo2curves::pasta::Fp>());
+    }
+
+    /// RMD160Circuit::<1> with the chip configured on columns of the test
+    #[derive(Clone, Debug)]
+    struct SharedColumnsCircuit(RMD160Circuit<1>);
+
+    impl Circuit<Fr> for SharedColumnsCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            SharedColumnsCircuit(self.0.without_witnesses())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let advice = [0; 8].map(|_| meta.advice_column());
+            let fixed = [meta.fixed_column()];
+            let range = RangeTable::configure(meta);
+            let functions = RoundFunctionTable::configure(meta);
+            meta.enable_equality(advice[0]);
+            Self::Config {
+               rmd160config: RMD160Chip::<Fr>::configure_with(meta, &advice[1..], &fixed, &range, &functions),
+               helperconfig: HelperChipConfig { limb: advice[0] },
+               instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            self.0.synthesize(config, layouter)
+        }
+    }
+
+    /// RMD160Circuit::<1> with the helper chip writing to one of the
+    /// advice columns of the rmd160 chip
+    #[derive(Clone, Debug)]
+    struct OwnColumnsCircuit(RMD160Circuit<1>);
+
+    impl Circuit<Fr> for OwnColumnsCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            OwnColumnsCircuit(self.0.without_witnesses())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let witness = [0; WITNESS_COLS].map(|_| meta.advice_column());
+            let fixed = [meta.fixed_column()];
+            let range = RangeTable::configure(meta);
+            let functions = RoundFunctionTable::configure(meta);
+            let rmd160config = RMD160Chip::<Fr>::configure_with(meta, &witness, &fixed, &range, &functions);
+            assert_eq!(rmd160config.witness, witness);
+            Self::Config {
+               rmd160config,
+               helperconfig: HelperChipConfig { limb: witness[0] },
+               instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            self.0.synthesize(config, layouter)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct TwoChipsConfig {
+        first: RMD160Config,
+        second: RMD160Config,
+        instance: Column<Instance>,
+    }
+
+    /// two chips on their own columns sharing the lookup tables
+    #[derive(Clone, Debug, Default)]
+    struct TwoChipsCircuit {
+        messages: [Vec<u8>; 2],
+    }
+
+    impl Circuit<Fr> for TwoChipsCircuit {
+        type Config = TwoChipsConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            TwoChipsCircuit { messages: self.messages.clone().map(|m| vec![0; m.len()]) }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            let range = RangeTable::configure(meta);
+            let functions = RoundFunctionTable::configure(meta);
+            let mut chip_config = || {
+                let advice = [0; WITNESS_COLS].map(|_| meta.advice_column());
+                let fixed = [meta.fixed_column()];
+                RMD160Chip::<Fr>::configure_with(meta, &advice, &fixed, &range, &functions)
+            };
+            let first = chip_config();
+            let second = chip_config();
+            TwoChipsConfig { first, second, instance }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            assert_eq!(config.first.range_table().column(), config.second.range_table().column());
+            assert_eq!(
+                config.first.round_function_table().columns(),
+                config.second.round_function_table().columns()
+            );
+            let chips = [config.first, config.second].map(|c| RMD160Chip::<Fr>::new(c));
+            chips[0].load_range_table(&mut layouter)?;
+            for (i, (chip, message)) in chips.iter().zip(self.messages.iter()).enumerate() {
+                let digest = chip.hash_bytes(&mut layouter, message)?;
+                digest.verify_against_instance(&mut layouter, config.instance, i * 5)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_shared_range_table() {
+        let messages = [b"first chip".to_vec(), vec![0xee; 70]];
+        let instances = messages.iter()
+            .flat_map(|m| digest_instances(rmd160_digest(m)))
+            .collect::<Vec<_>>();
+        let test_circuit = TwoChipsCircuit { messages };
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_configure_with_columns() {
+        let inputs = [[7u32; 16]];
+        let instances = host_digest(&inputs[0]);
+        let standalone = RMD160Circuit { inputs };
+        let shared = SharedColumnsCircuit(standalone.clone());
+        let prover = MockProver::run(K, &standalone, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(K, &shared, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let own = OwnColumnsCircuit(standalone);
+        let prover = MockProver::run(K, &own, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // no advice column besides the ones of the chip
+        let mut cs = ConstraintSystem::<Fr>::default();
+        OwnColumnsCircuit::configure(&mut cs);
+        assert_eq!(cs.num_advice_columns(), WITNESS_COLS);
+
+        let mut wrong = instances;
+        wrong[0] += Fr::one();
+        let prover = MockProver::run(K, &shared, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_gate_names() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        RMD160Chip::<Fr>::configure(&mut cs);
+        let names = cs.gates().iter().map(|g| g.name()).collect::<Vec<_>>();
+        assert_eq!(names.len(), RMD160Chip::<Fr>::gate_names().len());
+        assert_eq!(names, RMD160Chip::<Fr>::gate_names());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "only 7 are configured")]
+    fn test_witness_column_bound() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let config = RMD160Chip::<Fr>::configure(&mut cs);
+        cs.create_gate("out of bound", |meta| {
+            vec![config.get_expr(meta, GateCell::adv(WITNESS_COLS, 0, "bad"))]
+        });
+    }
+
+    #[test]
+    fn test_goldilocks_width() {
+        const GOLDILOCKS_P: u128 = (1u128 << 64) - (1u128 << 32) + 1;
+        assert!(check_field_bits(64).is_ok());
+        // largest round sum and its reconstruction w0 + wc * 2^32 stay below p
+        let max_sum = 4 * (u32::MAX as u128);
+        let (w0, wc) = carry_split(max_sum as u64);
+        assert_eq!(w0 as u128 + ((wc as u128) << 32), max_sum);
+        assert!(max_sum < GOLDILOCKS_P);
+    }
+
+    /// native replay of the round witnesses assigned by assign_block
+    fn witness_trace(start: [u32; 5], words: [u32; 16]) -> String {
+        let mut trace = String::new();
+        for (line, o, r, offset, pround) in [
+            ("left", &O, &R, &ROUNDS_OFFSET, false),
+            ("right", &PO, &PR, &PROUNDS_OFFSET, true),
+        ] {
+            let mut rol = start;
+            for round in 0..5 {
+                for index in 0..16 {
+                    let w = get_witnesses::<Fr, Rmd160Spec>(round, &rol, words[o[round][index]], r[round][index], offset[round], pround);
+                    trace.push_str(&format!(
+                        "{} {} {} r={} w0={} wb={} wc={} w1={} w1_h={} w1_l={} a_next={} w2b={} w2c={} w4_h={} w4_l={} c_next={}\n",
+                        line, round, index, w.r, w.w0, field_to_u64(&w.wb), w.wc.get(), w.w1, w.w1_h, w.w1_l,
+                        w.a_next, field_to_u64(&w.w2b), w.w2c.get(), w.w4_h, w.w4_l, w.c_next,
+                    ));
+                    rol = [rol[4], w.a_next, rol[1], w.c_next, rol[3]];
+                }
+            }
+        }
+        trace
+    }
+
+    /// run both lines through get_witnesses with the tables of the spec
+    fn spec_lines<S: HashSpec>(start: [u32; 5], words: [u32; 16]) -> [[u32; 5]; 2] {
+        [false, true].map(|pround| {
+            let mut rol = start;
+            for round in 0..S::ROUNDS {
+                for index in 0..16 {
+                    let x = words[S::orders(pround)[round][index]];
+                    let shift = S::rotations(pround)[round][index];
+                    let w = get_witnesses::<Fr, S>(round, &rol, x, shift, S::offsets(pround)[round], pround);
+                    rol = [rol[4], w.a_next, rol[1], w.c_next, rol[3]];
+                }
             }
+            rol
+        })
+    }
+
+    #[test]
+    fn test_spec_driven_lines() {
+        let words = [0x80636261, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 24, 0];
+        let [left, right] = spec_lines::<Rmd160Spec>(H0, words);
+        let (host_left, host_right) = compress_lines(&H0.to_vec(), &words.to_vec());
+        assert_eq!(left.to_vec(), host_left);
+        assert_eq!(right.to_vec(), host_right);
+        assert_eq!(Rmd160Spec::iv(), &H0[..]);
+        assert_eq!(Rmd160Spec::STATE_WORDS, 5);
+    }
+
+    #[test]
+    fn test_spec_circuit_digest() {
+        // the former constants stay the tables the spec hands out
+        assert_eq!(Rmd160Spec::rotations(false), &R[..]);
+        assert_eq!(Rmd160Spec::rotations(true), &PR[..]);
+        assert_eq!(Rmd160Spec::orders(false), &O[..]);
+        assert_eq!(Rmd160Spec::orders(true), &PO[..]);
+        assert_eq!(Rmd160Spec::offsets(false), &ROUNDS_OFFSET[..]);
+        assert_eq!(Rmd160Spec::offsets(true), &PROUNDS_OFFSET[..]);
+
+        let inputs = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16];
+        let digest = rmd160_block_words(H0, inputs);
+        let low = digest[0..4].iter().rev()
+            .fold(Fr::zero(), |acc, w| acc * Fr::from(1u64 << 32) + Fr::from(*w as u64));
+        let instances = vec![low, Fr::from(digest[4] as u64)];
+        let prover = MockProver::run(K, &ChipCircuit(ContentCircuit { inputs }), vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// set RMD160_GENERATE_GOLDEN=1 to regenerate the golden file after an intended change
+    #[test]
+    fn test_witness_trace_golden() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/rmd160_trace.golden");
+        let trace = witness_trace(H0, [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16]);
+        if std::env::var("RMD160_GENERATE_GOLDEN").is_ok() {
+            std::fs::write(path, &trace).unwrap();
         }
+        let golden = std::fs::read_to_string(path).unwrap();
+        assert_eq!(trace.lines().count(), 160);
+        assert!(trace == golden, "witness trace differs from {}", path);
+    }
 
-        fn assign_w(
+    #[test]
+    fn test_carry_at_word_boundary() {
+        // r = 0 ^ 0 ^ 0 in round 0 thus a + x hits 2^32 exactly
+        let rol = [0xffff_ffff, 0, 0, 0, 0];
+        let witness = get_witnesses::<Fr, Rmd160Spec>(0, &rol, 1, 11, 0, false);
+        assert_eq!(witness.w0, 0);
+        assert_eq!(witness.wc, Carry(1));
+        assert_eq!(witness.wb, Fr::from(1u64 << 32));
+
+        assert_eq!(carry_split((1u64 << 32) - 1), (0xffff_ffff, 0));
+        assert_eq!(carry_split(1u64 << 32), (0, 1));
+        assert_eq!(carry_split(4 * 0xffff_ffffu64), (0xffff_fffc, 3));
+    }
+
+    #[test]
+    fn test_rotate_multipliers() {
+        for (shifts, offsets) in [(&R, &ROUNDS_OFFSET), (&PR, &PROUNDS_OFFSET)] {
+            for (round, row) in shifts.iter().enumerate() {
+                assert_eq!(field_to_u64(&Fr::from(offsets[round] as u64)), offsets[round] as u64);
+                for shift in row.iter() {
+                    let (w1_r, w1_rr) = rotate_multipliers(*shift);
+                    assert_eq!(w1_r, 2u64.pow(*shift));
+                    assert_eq!(w1_rr, 2u64.pow(32 - *shift));
+                    assert_eq!(field_to_u64(&Fr::from(w1_r)), w1_r);
+                    assert_eq!(field_to_u64(&Fr::from(w1_rr)), w1_rr);
+                }
+            }
+        }
+
+        // the rotations by 0 and 32 bits are the identity and the split
+        // still satisfies w0 = w1_h * w1_rr + w1_l and w1 = w1_l * w1_r + w1_h
+        for shift in [0, 32] {
+            let (w1_r, w1_rr) = rotate_multipliers(shift);
+            assert_eq!(w1_r * w1_rr, 1u64 << 32);
+            let w = get_witnesses::<Fr, Rmd160Spec>(0, &H0, 0x12345678, shift, 0, false);
+            assert_eq!(w.w1, w.w0);
+            assert_eq!(w.w1_h as u64 * w1_rr + w.w1_l as u64, w.w0 as u64);
+            assert_eq!(w.w1_l as u64 * w1_r + w.w1_h as u64, w.w1 as u64);
+        }
+    }
+
+    #[test]
+    fn test_precompute_all_witnesses() {
+        let blocks = crate::circuits::bench::dummy_blocks(3);
+        let witnesses = precompute_all_witnesses::<Fr>(&H0, &blocks);
+        assert_eq!(witnesses.len(), 3 * 160);
+
+        // each block starts from the chaining value of the previous ones
+        let mut state = H0;
+        for (block, chunk) in blocks.iter().zip(witnesses.chunks(160)) {
+            assert_eq!(block_witnesses::<Fr, Rmd160Spec>(&state, block), chunk);
+            state = compress(&state.to_vec(), block.to_vec()).try_into().unwrap();
+        }
+
+        // the first step of the parallel line starts again from the block iv
+        let w = step_witness::<Fr, Rmd160Spec>(&H0, blocks[0][PO[0][0]], 0, 0, true);
+        assert_eq!(witnesses[80], w);
+
+        // resumed from the chaining value of the first block
+        let state: [u32; 5] = compress(&H0.to_vec(), blocks[0].to_vec()).try_into().unwrap();
+        assert_eq!(precompute_all_witnesses::<Fr>(&state, &blocks[1..]), witnesses[160..]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_precompute_all_witnesses_threads() {
+        let blocks = crate::circuits::bench::dummy_blocks(8);
+        let with_threads = |n| {
+            rayon::ThreadPoolBuilder::new().num_threads(n).build().unwrap()
+                .install(|| precompute_all_witnesses::<Fr>(&H0, &blocks))
+        };
+        let single = with_threads(1);
+        assert_eq!(with_threads(2), single);
+        assert_eq!(with_threads(8), single);
+    }
+
+    #[test]
+    fn test_check_round_witness() {
+        let x = 0x12345678;
+        let w = get_witnesses::<Fr, Rmd160Spec>(3, &H0, x, R[3][5], ROUNDS_OFFSET[3], false);
+        assert_eq!(check_round_witness(&w, &H0, x, ROUNDS_OFFSET[3]), Ok(()));
+        assert_eq!(check_round_witness(&w, &H0, x + 1, ROUNDS_OFFSET[3]), Err(RoundEquation::Sum));
+        let mut rol = H0;
+        rol[4] += 1;
+        assert_eq!(check_round_witness(&w, &rol, x, ROUNDS_OFFSET[3]), Err(RoundEquation::SumW1));
+
+        // a carry which disagrees with the sum in the field
+        let mut carry = get_witnesses::<Fr, Rmd160Spec>(3, &H0, x, R[3][5], ROUNDS_OFFSET[3], false);
+        carry.wc = Carry(carry.wc.get() + 1);
+        assert_eq!(check_round_witness(&carry, &H0, x, ROUNDS_OFFSET[3]), Err(RoundEquation::Carry));
+        let mut carry = get_witnesses::<Fr, Rmd160Spec>(3, &H0, x, R[3][5], ROUNDS_OFFSET[3], false);
+        carry.a_next ^= 1;
+        assert_eq!(check_round_witness(&carry, &H0, x, ROUNDS_OFFSET[3]), Err(RoundEquation::Carry));
+
+        let mismatch = WitnessMismatch { round: 3, index: 5, pround: false, equation: RoundEquation::Sum };
+        assert!(matches!(Error::from(mismatch), Error::Synthesis));
+        assert!(matches!(
+            RMD160Error::from(mismatch),
+            RMD160Error::WitnessMismatch { round: 53, equation: "sum" }
+        ));
+        let mismatch = WitnessMismatch { pround: true, ..mismatch };
+        assert!(matches!(RMD160Error::from(mismatch), RMD160Error::WitnessMismatch { round: 133, .. }));
+    }
+
+    /// the digest is bound to the instance column of the rmd160 config itself
+    #[derive(Clone, Debug, Default)]
+    struct PublicDigestCircuit<const PUBLIC: bool> {
+        inputs: [u32; 16],
+    }
+
+    impl<const PUBLIC: bool> Circuit<Fr> for PublicDigestCircuit<PUBLIC> {
+        type Config = (RMD160Config, HelperChipConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let rmd160config = RMD160ConfigBuilder::new().enable_public_digest(PUBLIC).configure(meta);
+            (rmd160config, HelperChip::configure(meta))
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.0.clone());
+            assert_eq!(config.0.instance().is_some(), PUBLIC);
+            rmd160chip.load_range_table(&mut layouter)?;
+            let helperchip = HelperChip::new(config.1.clone());
+            let w = helperchip.assign_w(&mut layouter, &H0, 0)?;
+            let input = helperchip.assign_inputs(&mut layouter, &self.inputs, 0)?;
+            let digest = rmd160chip.assign_content(&mut layouter, &w, &input)?;
+            rmd160chip.constrain_digest_public(&mut layouter, &digest)
+        }
+    }
+
+    #[test]
+    fn test_constrain_digest_public() {
+        let inputs = [0x80636261, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 24, 0];
+        let instances = host_digest(&inputs);
+        let prover = MockProver::run(K, &PublicDigestCircuit::<true> { inputs }, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mut wrong = instances.clone();
+        wrong[2] += Fr::one();
+        let prover = MockProver::run(K, &PublicDigestCircuit::<true> { inputs }, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+
+        let result = MockProver::run(K, &PublicDigestCircuit::<false> { inputs }, vec![]);
+        assert!(matches!(result, Err(Error::Synthesis)));
+    }
+
+    /// hash one block given as bytes and record the digest bytes and the
+    /// bytes read back from the packed words
+    #[derive(Clone, Debug)]
+    struct BytesBlockCircuit {
+        block: [u8; 64],
+        digest: std::cell::RefCell<[u8; 20]>,
+        words: std::cell::RefCell<[u8; 64]>,
+    }
+
+    impl BytesBlockCircuit {
+        fn new(block: [u8; 64]) -> Self {
+            BytesBlockCircuit {
+                block,
+                digest: std::cell::RefCell::new([0; 20]),
+                words: std::cell::RefCell::new([0; 64]),
+            }
+        }
+    }
+
+    impl Circuit<Fr> for BytesBlockCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            BytesBlockCircuit::new([0; 64])
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            test_config(meta, RMD160ConfigBuilder::new())
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            rmd160chip.load_range_table(&mut layouter)?;
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(&mut layouter, &H0, 0)?;
+            let input = rmd160chip.assign_bytes_block(&mut layouter, &self.block)?;
+            for (chunk, cell) in self.words.borrow_mut().chunks_exact_mut(4).zip(input.iter()) {
+                chunk.copy_from_slice(&cell_to_bytes(cell));
+            }
+            let digest = rmd160chip.assign_content(&mut layouter, &w, &input)?;
+            *self.digest.borrow_mut() = digest_to_bytes(&digest);
+            AssignedDigest::from_cells(digest).verify_against_instance(&mut layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_bytes_block_abc() {
+        let mut block = [0u8; 64];
+        block[..4].copy_from_slice(&[b'a', b'b', b'c', 0x80]);
+        block[56] = 24;
+        let expected = [
+            0x8e, 0xb2, 0x08, 0xf7, 0xe0, 0x5d, 0x98, 0x7a, 0x9b, 0x04,
+            0x4a, 0x8e, 0x98, 0xc6, 0xb0, 0x87, 0xf1, 0x5a, 0x0b, 0xfc,
+        ];
+        assert_eq!(rmd160_digest(b"abc"), expected);
+
+        let instances = expected.chunks_exact(4)
+            .map(|w| Fr::from(u32::from_le_bytes(w.try_into().unwrap()) as u64))
+            .collect::<Vec<_>>();
+        let test_circuit = BytesBlockCircuit::new(block);
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        assert_eq!(*test_circuit.digest.borrow(), expected);
+        assert_eq!(*test_circuit.words.borrow(), block);
+    }
+
+    #[test]
+    fn test_cell_to_bytes_round_trip() {
+        let words = [
+            0, 1, 0xff, 0x100, 0x7fff_ffff, 0x8000_0000, 0xffff_fffe, u32::MAX,
+            0x12345678, 0x80706050, 0xdeadbeef, 0x00ff00ff, 0xff00ff00, 0x01010101, 0xa5a5a5a5, 0x5a5a5a5a,
+        ];
+        let block: [u8; 64] = words.map(u32::to_le_bytes).concat().try_into().unwrap();
+        let instances = rmd160_block_words(H0, words).map(|w| Fr::from(w as u64)).to_vec();
+        let test_circuit = BytesBlockCircuit::new(block);
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let read = *test_circuit.words.borrow();
+        for (w, bytes) in words.iter().zip(read.chunks_exact(4)) {
+            assert_eq!(u32::from_le_bytes(bytes.try_into().unwrap()), *w);
+        }
+    }
+
+    /// hash a raw message padded in circuit against its length witness
+    #[derive(Clone, Debug, Default)]
+    struct MessageCircuit {
+        message: Vec<u8>,
+        len: u64,
+    }
+
+    impl ChipTest for MessageCircuit {
+        fn without_witnesses(&self) -> Self {
+            MessageCircuit { message: vec![0; self.message.len()], len: 0 }
+        }
+
+        fn synthesize(
             &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
             layouter: &mut impl Layouter<Fr>,
-            inputs: &[u32; 5],
-            offset: usize,
-        ) -> Result<[AssignedCell<Fr, Fr>; 5], Error> {
-            layouter.assign_region(
-                || "leaf layer",
-                |mut region| {
-                    let mut r = vec![];
-                    for round in 0..5 {
-                        r.push(region.assign_advice(
-                            || format!("assign w"),
-                            self.config.limb,
-                            offset + round,
-                            || Value::known(Fr::from(inputs[round] as u64))
-                        )?);
-                    }
-                    Ok(r.try_into().unwrap())
-                }
-            )
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let w = helperchip.assign_w(layouter, &H0, 0)?;
+            let len = helperchip.assign_value(layouter, self.len)?;
+            let blocks = rmd160chip.assign_message(layouter, &len, &self.message)?;
+            let r = rmd160chip.assign_prepadded(layouter, &w, &blocks)?;
+            AssignedDigest::from_cells(r).verify_against_instance(layouter, config.instance, 0)
         }
+    }
 
-        fn assign_inputs(
+    #[test]
+    fn test_assign_message() {
+        for (len, num_blocks) in [(0, 1), (55, 1), (56, 2), (64, 2)] {
+            let message = (0..len).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+            assert_eq!(pad_message(&message).len(), num_blocks * 64);
+            let test_circuit = ChipCircuit(MessageCircuit { message: message.clone(), len: len as u64 });
+            let instances = digest_instances(rmd160_digest(&message));
+            let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "length {}", len);
+
+            // a length witness off by one contradicts the marker and the length field
+            let test_circuit = ChipCircuit(MessageCircuit { message, len: len as u64 + 1 });
+            let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+            assert!(prover.verify().is_err(), "length {}", len);
+        }
+    }
+
+    /// hash the padded message from H0 or from the given start state
+    #[derive(Clone, Debug, Default)]
+    struct BlocksCircuit {
+        message: Vec<u8>,
+        start: Option<[u32; 5]>,
+    }
+
+    impl ChipTest for BlocksCircuit {
+        fn without_witnesses(&self) -> Self {
+            BlocksCircuit { message: vec![0; self.message.len()], start: self.start.map(|_| [0; 5]) }
+        }
+
+        fn synthesize(
             &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
             layouter: &mut impl Layouter<Fr>,
-            inputs: &[Fr; 16],
-            offset: usize
-        ) -> Result<[AssignedCell<Fr, Fr>; 16], Error> {
-            layouter.assign_region(
-                || "leaf layer",
-                |mut region| {
-                    let mut r = vec![];
-                    for i in 0..16 {
-                        let cell = region.assign_advice(
-                            || format!("assign input"),
-                            self.config.limb,
-                            offset + i,
-                            || Value::known(inputs[i])
-                        )?;
-                        r.push(cell);
-                    }
-                    Ok(r.try_into().unwrap())
-                }
-            )
+        ) -> Result<(), Error> {
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            let start = match self.start {
+                Some(start) => Some(helperchip.assign_w(layouter, &start, 0)?),
+                None => None,
+            };
+            let mut blocks = vec![];
+            for block in pad_message(&self.message).chunks_exact(64) {
+                blocks.push(rmd160chip.assign_bytes_block(layouter, block.try_into().unwrap())?);
+            }
+            let digest = rmd160chip.assign_blocks(layouter, start.as_ref(), &blocks)?;
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
         }
     }
 
+    #[test]
+    fn test_assign_blocks() {
+        let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec();
+        assert_eq!(pad_message(&message).len(), 128);
+        let digest = rmd160_digest(&message);
+        assert_eq!(digest[..4], [0x12, 0xa0, 0x53, 0x38]);
+        let test_circuit = ChipCircuit(BlocksCircuit { message: message.clone(), start: None });
+        let prover = MockProver::run(K, &test_circuit, vec![digest_instances(digest)]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // an explicit start state replaces H0
+        let start = [1, 2, 3, 4, 5];
+        let expected = message_blocks(&message).iter()
+            .fold(start, |state, block| rmd160_block_words(state, *block));
+        let test_circuit = ChipCircuit(BlocksCircuit { message, start: Some(start) });
+        let instances = expected.iter().map(|w| Fr::from(*w as u64)).collect();
+        let prover = MockProver::run(K, &test_circuit, vec![instances]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// hash the padded message block by block with RMD160Hasher and check
+    /// the result against the single shot assign_blocks
     #[derive(Clone, Debug, Default)]
-    struct RMD160Circuit {
-        inputs: [Fr; 16],
+    struct HasherCircuit {
+        message: Vec<u8>,
     }
 
-    #[derive(Clone, Debug)]
-    struct TestConfig {
-        rmd160config: RMD160Config,
-        helperconfig: HelperChipConfig,
+    impl ChipTest for HasherCircuit {
+        fn without_witnesses(&self) -> Self {
+            HasherCircuit { message: vec![0; self.message.len()] }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut blocks = vec![];
+            for block in pad_message(&self.message).chunks_exact(64) {
+                blocks.push(rmd160chip.assign_bytes_block(layouter, block.try_into().unwrap())?);
+            }
+            let mut hasher = RMD160Hasher::new(rmd160chip);
+            for block in blocks.iter() {
+                hasher.update(layouter, block)?;
+            }
+            let digest = hasher.finalize(layouter)?;
+            let single_shot = rmd160chip.assign_blocks(layouter, None, &blocks)?;
+            for (a, b) in digest.iter().zip(single_shot.iter()) {
+                assert_eq!(cell_to_value(a), cell_to_value(b));
+            }
+            AssignedDigest::from_cells(digest).verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_hasher_updates() {
+        let message = (0..100u8).collect::<Vec<_>>();
+        assert_eq!(pad_message(&message).len(), 128);
+        let test_circuit = ChipCircuit(HasherCircuit { message: message.clone() });
+        let instances = digest_instances(rmd160_digest(&message));
+        let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let mut wrong = instances;
+        wrong[0] += Fr::one();
+        let prover = MockProver::run(K, &test_circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    /// corrupt one cell per gate and expect the failure to name that gate
+    #[test]
+    fn test_gate_negative_paths() {
+        let w = get_witnesses::<Fr, Rmd160Spec>(0, &H0, 0x12345678, R[0][0], ROUNDS_OFFSET[0], false);
+        let cases = [
+            ("sum with bound", RoundGate::wb(), w.wb + Fr::one()),
+            ("sum with w1 rol4", RoundGate::w2b(), w.w2b + Fr::one()),
+            ("limbs sum", RoundGate::blimb(0), Fr::from(((H0[1] & 0xff) ^ 1) as u64)),
+            ("c rotate", RoundGate::c_next(), Fr::from((w.c_next ^ 1) as u64)),
+            ("w0 rotate", RoundGate::w1_h(), Fr::from((w.w1_h ^ 1) as u64)),
+        ];
+        for (gate, gate_cell, value) in cases {
+            let errors = tampered_round_fr(vec![(gate_cell.cell[1], gate_cell.cell[2], value)]).unwrap_err();
+            assert!(errors.iter().any(|e| format!("{:?}", e).contains(&format!("\"{}\"", gate))), "{}: {:?}", gate, errors);
+        }
+
+        let inputs = [0x9e3779b9; 16];
+        let mut raised = host_digest(&inputs);
+        raised[4] += Fr::one();
+        let test_circuit = ChipCircuit(CompressTamperCircuit { inputs, tamper: true });
+        let errors = MockProver::run(K, &test_circuit, vec![raised]).unwrap().verify().unwrap_err();
+        assert!(errors.iter().any(|e| format!("{:?}", e).contains("\"compress\"")), "{:?}", errors);
     }
 
-    impl Circuit<Fr> for RMD160Circuit {
+    #[derive(Clone, Debug, Default)]
+    struct WideRangeCircuit {
+        input: u32,
+        tampered: Vec<(usize, usize, Fr)>,
+    }
+
+    impl Circuit<Fr> for WideRangeCircuit {
         type Config = TestConfig;
         type FloorPlanner = SimpleFloorPlanner;
 
         fn without_witnesses(&self) -> Self {
-            Self::default()
+            self.clone()
         }
 
         fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
-            Self::Config {
-               rmd160config: RMD160Chip::<Fr>::configure(meta),
-               helperconfig: HelperChip::configure(meta)
-            }
+            let wide = RangeTableConfig::configure(meta);
+            test_config(meta, RMD160ConfigBuilder::new().wide_range_table(wide))
         }
 
         fn synthesize(
@@ -785,27 +6394,122 @@ mod tests {
             mut layouter: impl Layouter<Fr>,
         ) -> Result<(), Error> {
             let rmd160chip = RMD160Chip::<Fr>::new(config.clone().rmd160config);
+            rmd160chip.load_range_table(&mut layouter)?;
             let helperchip = HelperChip::new(config.clone().helperconfig);
-            let w= helperchip.assign_w(&mut layouter, &H0, 0)?;
-            let input = helperchip.assign_inputs(&mut layouter, &self.inputs, 0)?;
-            let r = rmd160chip.assign_content(&mut layouter, &w, &input)?;
-            println!("{} {} {} {} {}",
-                cell_to_u32(&r[0]),
-                cell_to_u32(&r[1]),
-                cell_to_u32(&r[2]),
-                cell_to_u32(&r[3]),
-                cell_to_u32(&r[4]),
-            );
-            Ok(())
+            let w = helperchip.assign_w(&mut layouter, &H0, 0)?;
+            let input = helperchip.assign_value(&mut layouter, self.input as u64)?;
+            layouter.assign_region(
+                || "wide range round",
+                |mut region| {
+                    let witness = step_witness::<Fr, Rmd160Spec>(&H0, self.input, 0, 0, false);
+                    let _ = rmd160chip.assign_next::<Rmd160Spec>(&mut region, 0, &w, &input, 0, 0, false, &witness)?;
+                    for (col, row, value) in self.tampered.iter() {
+                        rmd160chip.assign_cell(&mut region, 0, GateCell::adv(*col, *row, "tampered"), *value)?;
+                    }
+                    Ok(())
+                }
+            )
         }
     }
 
-
     #[test]
-    fn test_rmd160_circuit() {
-        let test_circuit = RMD160Circuit {inputs: [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16].map(|x| Fr::from(x as u64))} ;
-        let prover = MockProver::run(16, &test_circuit, vec![]).unwrap();
+    fn test_wide_range_table() {
+        let lookups = |builder: RMD160ConfigBuilder| {
+            let mut cs = ConstraintSystem::<Fr>::default();
+            builder.configure(&mut cs);
+            cs.lookups().len()
+        };
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let wide = RangeTableConfig::configure(&mut cs);
+        let bytes = lookups(RMD160ConfigBuilder::new());
+        let pairs = lookups(RMD160ConfigBuilder::new().wide_range_table(wide));
+        assert_eq!(bytes - pairs, 6);
+
+        // 2^16 table rows need k = 17
+        let test_circuit = WideRangeCircuit { input: 0x12345678, tampered: vec![] };
+        let prover = MockProver::run(K + 1, &test_circuit, vec![vec![]]).unwrap();
         assert_eq!(prover.verify(), Ok(()));
+
+        // move 2^16 from the third limb of b into the second one: the limbs
+        // and the nibbles still sum up but the low pair exceeds 16 bits
+        let bytes = 0xefcdab89u32.to_le_bytes();
+        let limbs = [bytes[0] as u64, bytes[1] as u64 + 256, bytes[2] as u64 - 1, bytes[3] as u64];
+        let mut tampered = vec![];
+        for i in 0..4 {
+            let cell = RoundGate::blimb(i).cell;
+            tampered.push((cell[1], cell[2], Fr::from(limbs[i])));
+        }
+        let nibble = RoundGate::bnibble(3).cell;
+        tampered.push((nibble[1], nibble[2], Fr::from((bytes[1] >> 4) as u64 + 16)));
+        let test_circuit = WideRangeCircuit { input: 0x12345678, tampered };
+        let errors = MockProver::run(K + 1, &test_circuit, vec![vec![]]).unwrap().verify().unwrap_err();
+        assert!(format!("{:?}", errors).contains("b limb range"), "{:?}", errors);
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct HmacCircuit {
+        key: Vec<u64>,
+        message: Vec<u8>,
+    }
+
+    impl ChipTest for HmacCircuit {
+        fn without_witnesses(&self) -> Self {
+            HmacCircuit { key: vec![0; self.key.len()], message: vec![0; self.message.len()] }
+        }
+
+        fn synthesize(
+            &self,
+            rmd160chip: &RMD160Chip<Fr>,
+            config: TestConfig,
+            layouter: &mut impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let key = layouter.assign_region(
+                || "key bytes",
+                |mut region| {
+                    self.key.iter().enumerate()
+                        .map(|(i, k)| rmd160chip.assign_cell(&mut region, i, GateCell::adv(0, 0, "key"), Fr::from(*k)))
+                        .collect::<Result<Vec<_>, Error>>()
+                }
+            )?;
+            let (_, mac) = rmd160chip.hmac_rmd160(layouter, &key, &self.message)?;
+            mac.verify_against_instance(layouter, config.instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_hmac_rmd160() {
+        // RFC 2286 test cases 1 and 2
+        let cases = [
+            (vec![0x0b; 20], b"Hi There".to_vec(), [
+                0x24, 0xcb, 0x4b, 0xd6, 0x7d, 0x20, 0xfc, 0x1a, 0x5d, 0x2e,
+                0xd7, 0x73, 0x2d, 0xcc, 0x39, 0x37, 0x7f, 0x0a, 0x56, 0x68,
+            ]),
+            (b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec(), [
+                0xdd, 0xa6, 0xc0, 0x21, 0x3a, 0x48, 0x5a, 0x9e, 0x24, 0xf4,
+                0x74, 0x20, 0x64, 0xa7, 0xf0, 0x33, 0xb4, 0x3c, 0x40, 0x69,
+            ]),
+        ];
+        for (key, message, mac) in cases {
+            assert_eq!(hmac_rmd160(&key, &message), mac);
+            let test_circuit = ChipCircuit(HmacCircuit { key: key.iter().map(|k| *k as u64).collect(), message: message.clone() });
+            let instances = digest_instances(mac);
+            let prover = MockProver::run(K, &test_circuit, vec![instances.clone()]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            let mut wrong = instances;
+            wrong[1] += Fr::one();
+            let prover = MockProver::run(K, &test_circuit, vec![wrong]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        let test_circuit = ChipCircuit(HmacCircuit { key: vec![0; 65], message: vec![] });
+        assert!(MockProver::run(K, &test_circuit, vec![vec![]]).is_err());
+
+        // a key byte of 256 has no 8 bit decomposition
+        let test_circuit = ChipCircuit(HmacCircuit { key: vec![0x100], message: b"abc".to_vec() });
+        let instances = digest_instances(hmac_rmd160(&[0], b"abc"));
+        let errors = MockProver::run(K, &test_circuit, vec![instances]).unwrap().verify().unwrap_err();
+        assert!(errors.iter().any(|e| format!("{:?}", e).contains("\"hmac key\"")), "{:?}", errors);
     }
 }
 
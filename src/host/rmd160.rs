@@ -1,3 +1,9 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::debug_trace;
+
 pub const DIGEST_BUF_LEN: usize = 5;
 pub const WORK_BUF_LEN: usize = 16;
 pub const H0: [u32; DIGEST_BUF_LEN] = [
@@ -8,6 +14,9 @@ pub const H0: [u32; DIGEST_BUF_LEN] = [
     0xc3d2_e1f0,
 ];
 
+/// The five bitwise boolean functions of RIPEMD-160. atomic selects one
+/// by its index 0 .. 5 for f, g, h, i, j, the left line uses them in this
+/// order over its five rounds (see HashSpec::function for the other line).
 pub trait RMD160Atomic{
     fn f(x: Self, y: Self, z: Self) -> Self;
     fn g(x: Self, y: Self, z: Self) -> Self;
@@ -62,81 +71,386 @@ fn rol_modifier(round: usize, rol: &mut Vec<u32>, x: u32, offset: u32, shift: u3
     rol[2] = rol[2].rotate_left(10);
 }
 
-pub(crate) const ROUNDS_OFFSET: [u32; DIGEST_BUF_LEN] = [
-    0x0, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e,
-];
+/// the tables of Rmd160Spec under their former names, kept for the code
+/// written against them; the hashing code reads them through RoundSchedule
+pub const ROUNDS_OFFSET: [u32; DIGEST_BUF_LEN] = Rmd160Spec::ROUNDS_OFFSET;
+pub const PROUNDS_OFFSET: [u32; DIGEST_BUF_LEN] = Rmd160Spec::PROUNDS_OFFSET;
+pub const R: [[u32; 16]; DIGEST_BUF_LEN] = Rmd160Spec::R;
+pub const O: [[usize; 16]; DIGEST_BUF_LEN] = Rmd160Spec::O;
+pub const PR: [[u32; 16]; DIGEST_BUF_LEN] = Rmd160Spec::PR;
+pub const PO: [[usize; 16]; DIGEST_BUF_LEN] = Rmd160Spec::PO;
+
+/// How the outputs of the left and the parallel line are folded back into
+/// the chaining state at the end of a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combination {
+    /// both lines run on the whole state and every new word is the sum of
+    /// one word of the state and one of each line, taken at staggered
+    /// positions: h'[k] = h[k+1] + left[k+2] + right[k+3] (RIPEMD-128/160)
+    Cross,
+    /// the lines run on their own halves of a double width state, which
+    /// are added to the matching halves of the state (RIPEMD-256/320),
+    /// the word exchanges between the lines happen inside the rounds
+    Concat,
+}
 
-pub(crate) const PROUNDS_OFFSET: [u32; DIGEST_BUF_LEN] = [
-    0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x0
-];
+impl Combination {
+    /// the new chaining state from the state and the outputs of both lines
+    pub fn combine(&self, state: &[u32], left: &[u32], right: &[u32]) -> Vec<u32> {
+        match self {
+            Combination::Cross => {
+                let n = state.len();
+                (0..n).map(|k| state[(k + 1) % n]
+                    .wrapping_add(left[(k + 2) % n])
+                    .wrapping_add(right[(k + 3) % n]))
+                    .collect()
+            },
+            Combination::Concat => {
+                let half = state.len() / 2;
+                state.iter().enumerate().map(|(k, h)| {
+                    let line = if k < half { left[k] } else { right[k - half] };
+                    h.wrapping_add(line)
+                }).collect()
+            },
+        }
+    }
+}
 
-/*round1*/
-pub(crate) const R: [[u32; 16]; DIGEST_BUF_LEN] = [
-    [11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8],
-    [7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15, 9, 11, 7, 13, 12],
-    [11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5],
-    [11, 12, 14, 15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12],
-    [9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6],
-];
+/// The parameters that tell the members of the RIPEMD family apart: the
+/// rotation amounts, the message word orders and the round constants of
+/// both lines, the initial state and the width of the state. The round
+/// machinery of the circuit reads these instead of the RIPEMD-160 tables.
+pub trait HashSpec {
+    /// words of the chaining state, the digest has 4 * STATE_WORDS bytes
+    const STATE_WORDS: usize;
+    /// rounds of 16 steps in each line
+    const ROUNDS: usize;
+
+    fn iv() -> &'static [u32];
+    /// rotation amounts per round and step of the left or the parallel (pround) line
+    fn rotations(pround: bool) -> &'static [[u32; 16]];
+    /// message word order per round and step of either line
+    fn orders(pround: bool) -> &'static [[usize; 16]];
+    /// additive constant per round of either line
+    fn offsets(pround: bool) -> &'static [u32];
+
+    /// how the two lines are combined at the end of a block
+    const COMBINATION: Combination;
+
+    /// the new chaining state from the state and the outputs of both lines
+    fn combine(state: &[u32], left: &[u32], right: &[u32]) -> Vec<u32> {
+        Self::COMBINATION.combine(state, left, right)
+    }
 
-pub(crate) const O: [[usize; 16]; DIGEST_BUF_LEN] = [
-    [0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15],
-    [7,4,13,1,10,6,15,3,12,0,9,5,2,14,11,8],
-    [3,10,14,4,9,15,8,1,2,7,0,6,13,11,5,12],
-    [1,9,11,10,0,8,12,4,13,3,7,15,14,5,6,2],
-    [4,0,5,9,7,12,2,10,14,1,3,8,11,6,15,13],
-];
+    /// the boolean function (0 .. 5 for f, g, h, i, j) of a round. The
+    /// specification numbers the steps 0 .. 80 and gives step t of the
+    /// parallel line the function f(79 - t), so its rounds run j, i, h, g, f:
+    /// the reverse of the left line, hence ROUNDS - 1 - round.
+    fn function(round: usize, pround: bool) -> usize {
+        if pround { Self::ROUNDS - 1 - round } else { round }
+    }
+}
 
-/*parallelround1*/
-pub(crate) const PR: [[u32; 16]; DIGEST_BUF_LEN] = [
-    [8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6],
-    [9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12, 7, 6, 15, 13, 11],
-    [9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5],
-    [15, 5, 8, 11, 14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8],
-    [8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11],
-];
+pub struct Rmd160Spec;
+
+impl Rmd160Spec {
+    pub const ROUNDS_OFFSET: [u32; DIGEST_BUF_LEN] = [
+        0x0, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e,
+    ];
+
+    pub const PROUNDS_OFFSET: [u32; DIGEST_BUF_LEN] = [
+        0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x0
+    ];
+
+    /*round1*/
+    pub const R: [[u32; 16]; DIGEST_BUF_LEN] = [
+        [11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8],
+        [7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15, 9, 11, 7, 13, 12],
+        [11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5],
+        [11, 12, 14, 15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12],
+        [9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6],
+    ];
+
+    pub const O: [[usize; 16]; DIGEST_BUF_LEN] = [
+        [0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15],
+        [7,4,13,1,10,6,15,3,12,0,9,5,2,14,11,8],
+        [3,10,14,4,9,15,8,1,2,7,0,6,13,11,5,12],
+        [1,9,11,10,0,8,12,4,13,3,7,15,14,5,6,2],
+        [4,0,5,9,7,12,2,10,14,1,3,8,11,6,15,13],
+    ];
+
+    /*parallelround1*/
+    pub const PR: [[u32; 16]; DIGEST_BUF_LEN] = [
+        [8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6],
+        [9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12, 7, 6, 15, 13, 11],
+        [9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5],
+        [15, 5, 8, 11, 14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8],
+        [8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11],
+    ];
+
+    pub const PO: [[usize; 16]; DIGEST_BUF_LEN] = [
+        [5,14,7,0,9,2,11,4,13,6,15,8,1,10,3,12],
+        [6,11,3,7,0,13,5,10,14,15,8,12,4,9,1,2],
+        [15,5,1,3,7,14,6,9,11,8,12,2,10,0,4,13],
+        [8,6,4,1,3,11,15,0,5,12,2,13,9,7,10,14],
+        [12,15,10,4,1,5,8,7,6,2,13,14,0,3,9,11],
+    ];
+}
 
-pub(crate) const PO: [[usize; 16]; DIGEST_BUF_LEN] = [
-    [5,14,7,0,9,2,11,4,13,6,15,8,1,10,3,12],
-    [6,11,3,7,0,13,5,10,14,15,8,12,4,9,1,2],
-    [15,5,1,3,7,14,6,9,11,8,12,2,10,0,4,13],
-    [8,6,4,1,3,11,15,0,5,12,2,13,9,7,10,14],
-    [12,15,10,4,1,5,8,7,6,2,13,14,0,3,9,11],
-];
+impl HashSpec for Rmd160Spec {
+    const STATE_WORDS: usize = DIGEST_BUF_LEN;
+    const ROUNDS: usize = 5;
+    const COMBINATION: Combination = Combination::Cross;
 
-pub fn compress(w: &Vec<u32>, values: Vec<u32>) -> Vec<u32> {
+    fn iv() -> &'static [u32] {
+        &H0
+    }
+
+    fn rotations(pround: bool) -> &'static [[u32; 16]] {
+        if pround { &Self::PR } else { &Self::R }
+    }
+
+    fn orders(pround: bool) -> &'static [[usize; 16]] {
+        if pround { &Self::PO } else { &Self::O }
+    }
+
+    fn offsets(pround: bool) -> &'static [u32] {
+        if pround { &Self::PROUNDS_OFFSET } else { &Self::ROUNDS_OFFSET }
+    }
+}
+
+/// The rotation amounts, message word orders and round constants of both
+/// lines of a spec behind accessors named after the line they belong to,
+/// so a round can not pick up the order of the other line by mistake.
+#[derive(Clone, Copy, Debug)]
+pub struct RoundSchedule {
+    rounds: usize,
+    left_shifts: &'static [[u32; 16]],
+    left_orders: &'static [[usize; 16]],
+    left_offsets: &'static [u32],
+    right_shifts: &'static [[u32; 16]],
+    right_orders: &'static [[usize; 16]],
+    right_offsets: &'static [u32],
+}
+
+impl RoundSchedule {
+    pub fn of<S: HashSpec>() -> Self {
+        RoundSchedule {
+            rounds: S::ROUNDS,
+            left_shifts: S::rotations(false),
+            left_orders: S::orders(false),
+            left_offsets: S::offsets(false),
+            right_shifts: S::rotations(true),
+            right_orders: S::orders(true),
+            right_offsets: S::offsets(true),
+        }
+    }
+
+    pub fn rmd160() -> Self {
+        Self::of::<Rmd160Spec>()
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn left_shift(&self, round: usize, index: usize) -> u32 {
+        self.left_shifts[round][index]
+    }
+
+    pub fn left_order(&self, round: usize) -> &'static [usize; 16] {
+        &self.left_orders[round]
+    }
+
+    pub fn left_offset(&self, round: usize) -> u32 {
+        self.left_offsets[round]
+    }
+
+    pub fn right_shift(&self, round: usize, index: usize) -> u32 {
+        self.right_shifts[round][index]
+    }
+
+    pub fn right_order(&self, round: usize) -> &'static [usize; 16] {
+        &self.right_orders[round]
+    }
+
+    pub fn right_offset(&self, round: usize) -> u32 {
+        self.right_offsets[round]
+    }
+
+    /// the rotation of step index of the left or the parallel (pround) line
+    pub fn shift(&self, pround: bool, round: usize, index: usize) -> u32 {
+        if pround { self.right_shift(round, index) } else { self.left_shift(round, index) }
+    }
+
+    /// the message word order of a round of either line
+    pub fn order(&self, pround: bool, round: usize) -> &'static [usize; 16] {
+        if pround { self.right_order(round) } else { self.left_order(round) }
+    }
+
+    /// the additive constant of a round of either line
+    pub fn offset(&self, pround: bool, round: usize) -> u32 {
+        if pround { self.right_offset(round) } else { self.left_offset(round) }
+    }
+
+    /// check the tables have a row per round, every order is a permutation
+    /// of the 16 message words and every rotation is in 1 .. 32
+    pub fn validate(&self) -> Result<(), String> {
+        for (line, shifts, orders, offsets) in [
+            ("left", self.left_shifts, self.left_orders, self.left_offsets),
+            ("right", self.right_shifts, self.right_orders, self.right_offsets),
+        ] {
+            if shifts.len() != self.rounds || orders.len() != self.rounds || offsets.len() != self.rounds {
+                return Err(format!("{} line does not have {} rounds", line, self.rounds));
+            }
+            for round in 0..self.rounds {
+                let mut seen = [false; 16];
+                for idx in orders[round] {
+                    if idx >= 16 || seen[idx] {
+                        return Err(format!("{} order of round {} is not a permutation", line, round));
+                    }
+                    seen[idx] = true;
+                }
+                if let Some(index) = shifts[round].iter().position(|s| *s == 0 || *s >= 32) {
+                    return Err(format!("{} shift {} of round {} out of range", line, index, round));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// run the left and the parallel line on a block without combining them
+pub fn compress_lines(w: &Vec<u32>, values: &Vec<u32>) -> (Vec<u32>, Vec<u32>) {
+    let schedule = RoundSchedule::rmd160();
     let mut rol1 = w.clone();
     let mut rol2 = w.clone();
-    let mut round = 0;
-    for ((idxs,shift), offset) in O.zip(R).zip(ROUNDS_OFFSET) {
-        for limb_index in 0..16 {
-            let idx = idxs[limb_index];
-            rol_modifier(round, &mut rol1, values[idx], offset, shift[limb_index]);
+    for round in 0..schedule.rounds() {
+        for (index, idx) in schedule.left_order(round).iter().enumerate() {
+            rol_modifier(round, &mut rol1, values[*idx], schedule.left_offset(round), schedule.left_shift(round, index));
             rol1.rotate_right(1);
         }
-        round += 1;
     }
 
-    for ((idxs,shift), offset) in PO.zip(PR).zip(PROUNDS_OFFSET) {
-        for limb_index in 0..16 {
-            let idx = idxs[limb_index];
-            rol_modifier(round-1, &mut rol2, values[idx], offset, shift[limb_index]);
+    for round in 0..schedule.rounds() {
+        let f = Rmd160Spec::function(round, true);
+        for (index, idx) in schedule.right_order(round).iter().enumerate() {
+            rol_modifier(f, &mut rol2, values[*idx], schedule.right_offset(round), schedule.right_shift(round, index));
             rol2.rotate_right(1);
         }
-        round -= 1;
     }
+    (rol1, rol2)
+}
 
-    println!("{:?}, {:?}", rol1, rol2);
-    let mut r = vec![];
-    let len = w.len();
-    for i in 0..w.len() {
-        r.push(w[i].wrapping_add(rol1[(i+1)%len]).wrapping_add(rol2[(i+2)%len]));
-    }
-    r.rotate_left(1);
-    println!("compressed {:?}", r);
+pub fn compress(w: &Vec<u32>, values: Vec<u32>) -> Vec<u32> {
+    let (rol1, rol2) = compress_lines(w, &values);
+    debug_trace!("{:?}, {:?}", rol1, rol2);
+    let r = Rmd160Spec::combine(w, &rol1, &rol2);
+    debug_trace!("compressed {:?}", r);
     r
 }
 
+/// compress one block of 16 words from the given state
+pub fn rmd160_block_words(state: [u32; DIGEST_BUF_LEN], words: [u32; WORK_BUF_LEN]) -> [u32; DIGEST_BUF_LEN] {
+    compress(&state.to_vec(), words.to_vec()).try_into().unwrap()
+}
+
+/// the message followed by the 0x80 marker, the zeros up to 56 mod 64 and
+/// the 64 bit little endian bit length, as expected by RMD160Chip::assign_padding
+pub fn pad_message(data: &[u8]) -> Vec<u8> {
+    let mut bytes = data.to_vec();
+    bytes.push(0x80);
+    while bytes.len() % 64 != 56 {
+        bytes.push(0);
+    }
+    bytes.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+    bytes
+}
+
+/// compress one block of 64 bytes from the given state, the words of
+/// the block are read in little endian
+pub fn compress_block(state: [u32; DIGEST_BUF_LEN], block: &[u8; 64]) -> [u32; DIGEST_BUF_LEN] {
+    let words = block.chunks_exact(4)
+        .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    rmd160_block_words(state, words.try_into().unwrap())
+}
+
+/// pad the message (0x80, zeros, 64 bit little endian bit length)
+/// and split it into the 16 word blocks consumed by compress, this is
+/// the exact block sequence expected by RMD160Chip::assign_prepadded
+pub fn message_blocks(data: &[u8]) -> Vec<[u32; WORK_BUF_LEN]> {
+    pad_message(data).chunks_exact(64)
+        .map(|block| block.chunks_exact(4)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap())
+        .collect()
+}
+
+/// hash a whole message and return the 20 bytes digest
+pub fn rmd160_digest(data: &[u8]) -> [u8; 20] {
+    hash_prepadded(&message_blocks(data))
+}
+
+/// hash blocks which already contain the RIPEMD-160 padding.
+/// No padding is applied here, so blocks that are not padded
+/// (or padded for another length) produce a wrong digest for the message.
+pub fn hash_prepadded(blocks: &[[u32; WORK_BUF_LEN]]) -> [u8; 20] {
+    let mut state = H0.to_vec();
+    for block in blocks {
+        state = compress(&state, block.to_vec());
+    }
+    state.into_iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<u8>>()
+        .try_into()
+        .unwrap()
+}
+
+/// HMAC-RIPEMD160 (RFC 2286) of the message under the key
+pub fn hmac_rmd160(key: &[u8], message: &[u8]) -> [u8; 20] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..20].copy_from_slice(&rmd160_digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |c: u8| block.iter().map(|k| k ^ c).collect::<Vec<u8>>();
+    let inner = rmd160_digest(&[pad(0x36), message.to_vec()].concat());
+    rmd160_digest(&[pad(0x5c), inner.to_vec()].concat())
+}
+
+/// domain tags of the Merkle tree, the leaves are hashed as
+/// rmd160(0x00 || leaf) and the parents as rmd160(0x01 || left || right)
+pub const MERKLE_LEAF_TAG: u8 = 0x00;
+pub const MERKLE_NODE_TAG: u8 = 0x01;
+
+pub fn merkle_leaf(leaf: &[u8]) -> [u8; 20] {
+    rmd160_digest(&[&[MERKLE_LEAF_TAG][..], leaf].concat())
+}
+
+pub fn merkle_node(left: &[u8; 20], right: &[u8; 20]) -> [u8; 20] {
+    rmd160_digest(&[&[MERKLE_NODE_TAG][..], &left[..], &right[..]].concat())
+}
+
+/// hash a leaf and its authentication path up to the root, index_bits[i]
+/// is set when the node at level i is the right child of its parent
+pub fn merkle_root(leaf: &[u8], path: &[[u8; 20]], index_bits: &[bool]) -> [u8; 20] {
+    assert_eq!(path.len(), index_bits.len());
+    path.iter().zip(index_bits).fold(merkle_leaf(leaf), |node, (sibling, is_right)| {
+        if *is_right { merkle_node(sibling, &node) } else { merkle_node(&node, sibling) }
+    })
+}
+
+/// hash independent messages in parallel, the output keeps the input order
+#[cfg(feature = "rayon")]
+pub fn par_rmd160(inputs: &[&[u8]]) -> Vec<[u8; 20]> {
+    use rayon::prelude::*;
+    inputs.par_iter().map(|data| rmd160_digest(data)).collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -431,7 +745,7 @@ mod tests {
     fn test_rmd160_compress() {
         let words = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16];
         let bytes:[u8; 64] = words.into_iter()
-            .map(|x:u32| x.to_le_bytes()).flatten()
+            .flat_map(|x:u32| x.to_le_bytes())
             .collect::<Vec<u8>>()
             .try_into()
             .unwrap();
@@ -440,5 +754,173 @@ mod tests {
         let mut r1 = super::H0.clone();
         compress(&mut r1, &bytes);
         assert_eq!(r.to_vec(), r1);
+        assert_eq!(super::compress_block(super::H0, &bytes), r1);
+    }
+
+    #[test]
+    fn test_rmd160_digest() {
+        let empty: [u8; 20] = [
+            0x9c, 0x11, 0x85, 0xa5, 0xc5, 0xe9, 0xfc, 0x54, 0x61, 0x28,
+            0x08, 0x97, 0x7e, 0xe8, 0xf5, 0x48, 0xb2, 0x25, 0x8d, 0x31,
+        ];
+        let abc: [u8; 20] = [
+            0x8e, 0xb2, 0x08, 0xf7, 0xe0, 0x5d, 0x98, 0x7a, 0x9b, 0x04,
+            0x4a, 0x8e, 0x98, 0xc6, 0xb0, 0x87, 0xf1, 0x5a, 0x0b, 0xfc,
+        ];
+        assert_eq!(super::rmd160_digest(b""), empty);
+        assert_eq!(super::rmd160_digest(b"abc"), abc);
+    }
+
+    #[test]
+    fn test_hmac_rmd160() {
+        // RFC 2286 test cases 3 and 6, the latter with a key longer than a block
+        let mac3: [u8; 20] = [
+            0xb0, 0xb1, 0x05, 0x36, 0x0d, 0xe7, 0x59, 0x96, 0x0a, 0xb4,
+            0xf3, 0x52, 0x98, 0xe1, 0x16, 0xe2, 0x95, 0xd8, 0xe7, 0xc1,
+        ];
+        let mac6: [u8; 20] = [
+            0x64, 0x66, 0xca, 0x07, 0xac, 0x5e, 0xac, 0x29, 0xe1, 0xbd,
+            0x52, 0x3e, 0x5a, 0xda, 0x76, 0x05, 0xb7, 0x91, 0xfd, 0x8b,
+        ];
+        assert_eq!(super::hmac_rmd160(&[0xaa; 20], &[0xdd; 50]), mac3);
+        assert_eq!(super::hmac_rmd160(&[0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First"), mac6);
+    }
+
+    #[test]
+    fn test_hash_prepadded() {
+        // "abc" padded by hand: 0x80 marker after the message and 24 bits of length
+        let mut block = [0u32; 16];
+        block[0] = u32::from_le_bytes([b'a', b'b', b'c', 0x80]);
+        block[14] = 24;
+        assert_eq!(super::hash_prepadded(&[block]), super::rmd160_digest(b"abc"));
+        assert_eq!(super::message_blocks(b"abc"), vec![block]);
+        // without padding the digest is different
+        assert_ne!(super::hash_prepadded(&[[0; 16]]), super::rmd160_digest(&[0; 64]));
+    }
+
+    #[test]
+    fn test_message_blocks() {
+        for len in [0, 1, 55, 56, 63, 64, 119, 120, 1000] {
+            let data = (0..len).map(|x| x as u8).collect::<Vec<_>>();
+            let blocks = super::message_blocks(&data);
+            let padded_len = (len + 8) / 64 * 64 + 64;
+            assert_eq!(blocks.len(), padded_len / 64);
+            assert_eq!(super::hash_prepadded(&blocks), super::rmd160_digest(&data));
+            let rehashed = blocks.iter()
+                .fold(super::H0, |state, block| super::rmd160_block_words(state, *block));
+            let bytes = rehashed.map(|x| x.to_le_bytes()).concat();
+            assert_eq!(bytes, super::rmd160_digest(&data).to_vec());
+        }
+    }
+
+    #[test]
+    fn test_round_schedule() {
+        let schedule = super::RoundSchedule::rmd160();
+        assert_eq!(schedule.validate(), Ok(()));
+        assert_eq!(schedule.rounds(), 5);
+
+        // the word orders are rho^round applied to the identity on the left
+        // and to pi(i) = 9i + 5 mod 16 on the right
+        let rho = [7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5, 2, 14, 11, 8];
+        let mut left: [usize; 16] = std::array::from_fn(|i| i);
+        let mut right: [usize; 16] = std::array::from_fn(|i| (9 * i + 5) % 16);
+        for round in 0..5 {
+            assert_eq!(schedule.left_order(round), &left);
+            assert_eq!(schedule.right_order(round), &right);
+            assert_eq!(schedule.order(false, round), &left);
+            assert_eq!(schedule.order(true, round), &right);
+            left = left.map(|i| rho[i]);
+            right = right.map(|i| rho[i]);
+        }
+
+        // the constants are the integer parts of 2^30 times the square and
+        // cube roots of 2, 3, 5 and 7, the first left and the last right are 0
+        for (i, p) in [2f64, 3.0, 5.0, 7.0].iter().enumerate() {
+            assert_eq!(schedule.left_offset(i + 1), (p.sqrt() * (1u64 << 30) as f64) as u32);
+            assert_eq!(schedule.right_offset(i), (p.cbrt() * (1u64 << 30) as f64) as u32);
+        }
+        assert_eq!(schedule.left_offset(0), 0);
+        assert_eq!(schedule.right_offset(4), 0);
+
+        // a few rotations from the tables of the specification
+        assert_eq!(schedule.left_shift(0, 0), 11);
+        assert_eq!(schedule.left_shift(4, 15), 6);
+        assert_eq!(schedule.right_shift(0, 0), 8);
+        assert_eq!(schedule.right_shift(4, 15), 11);
+        assert_eq!(schedule.shift(true, 2, 3), 11);
+        assert_eq!(schedule.offset(true, 1), 0x5c4dd124);
+
+        // an order with a repeated word is rejected
+        const BROKEN: [[usize; 16]; 5] = {
+            let mut o = super::Rmd160Spec::O;
+            o[2][0] = o[2][1];
+            o
+        };
+        let broken = super::RoundSchedule { left_orders: &BROKEN, ..schedule };
+        assert!(broken.validate().is_err());
+    }
+
+    /// truth tables of f, g, h, i, j: bit (x << 2 | y << 1 | z) is the value
+    /// of the function on the bits x, y and z
+    const TRUTH_TABLES: [u8; 5] = [0x96, 0xca, 0x59, 0xe4, 0x2d];
+
+    #[test]
+    fn test_atomic_truth_tables() {
+        use super::RMD160Atomic;
+        let word = |bit: usize| if bit == 1 { u32::MAX } else { 0 };
+        for (f, table) in TRUTH_TABLES.iter().enumerate() {
+            for idx in 0..8 {
+                let (x, y, z) = (word(idx >> 2 & 1), word(idx >> 1 & 1), word(idx & 1));
+                assert_eq!(u32::atomic(f, x, y, z), word((*table as usize >> idx) & 1),
+                    "function {} on {:03b}", f, idx);
+            }
+        }
+    }
+
+    #[test]
+    fn test_atomic_bitwise() {
+        use super::RMD160Atomic;
+        let words = [
+            0, u32::MAX, 0x0123_4567, 0x89ab_cdef, 0xaaaa_aaaa, 0x5555_5555,
+            0x8000_0001, 0xdead_beef, 0x67452301, 0xc3d2e1f0,
+        ];
+        for (f, table) in TRUTH_TABLES.iter().enumerate() {
+            for x in words {
+                for y in words {
+                    for z in words {
+                        let r = u32::atomic(f, x, y, z);
+                        for k in 0..32 {
+                            let idx = (x >> k & 1) << 2 | (y >> k & 1) << 1 | (z >> k & 1);
+                            assert_eq!(r >> k & 1, (*table as u32 >> idx) & 1,
+                                "function {} bit {} of ({:#x}, {:#x}, {:#x})", f, k, x, y, z);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_function_index() {
+        use super::{HashSpec, Rmd160Spec, RMD160Atomic};
+        let (x, y, z) = (0x0123_4567, 0x89ab_cdef, 0xfedc_ba98);
+        for round in 0..5 {
+            assert_eq!(Rmd160Spec::function(round, false), round);
+            assert_eq!(Rmd160Spec::function(round, true), 4 - round);
+        }
+        // the first parallel round runs j and the last one f
+        assert_eq!(u32::atomic(Rmd160Spec::function(0, true), x, y, z), u32::j(x, y, z));
+        assert_eq!(u32::atomic(Rmd160Spec::function(4, true), x, y, z), u32::f(x, y, z));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_rmd160() {
+        let messages = (0..64u8)
+            .map(|n| (0..n).collect::<Vec<u8>>())
+            .collect::<Vec<_>>();
+        let inputs = messages.iter().map(|m| m.as_slice()).collect::<Vec<_>>();
+        let serial = inputs.iter().map(|m| super::rmd160_digest(m)).collect::<Vec<_>>();
+        assert_eq!(super::par_rmd160(&inputs), serial);
     }
 }
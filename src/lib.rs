@@ -1,7 +1,14 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![feature(array_zip)]
 #![feature(slice_flatten)]
+
+// the host reference and the digest helpers only need alloc, the
+// circuits come with the std feature
+extern crate alloc;
+
 pub mod utils;
+#[cfg(feature = "std")]
 pub mod circuits;
 pub mod host;
+#[cfg(feature = "std")]
 pub use halo2_proofs::halo2curves::bn256::Fr;
-
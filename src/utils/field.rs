@@ -0,0 +1,150 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::AssignedCell;
+use num_bigint::BigUint;
+
+pub fn field_to_bn<F: FieldExt>(f: &F) -> BigUint {
+    let bytes = f.to_repr();
+    BigUint::from_bytes_le(bytes.as_ref())
+}
+
+pub fn bn_to_field<F: FieldExt>(bn: &BigUint) -> F {
+    let mut bytes = bn.to_bytes_le();
+    bytes.resize(64, 0);
+    F::from_bytes_wide(&bytes.try_into().unwrap())
+}
+
+pub fn field_to_u32<F: FieldExt>(f: &F) -> u32 {
+    f.get_lower_32()
+}
+
+/// the low 64 bits of f, the higher bits are silently dropped. Use
+/// field_to_u64_checked where f is not known to fit in a u64.
+pub fn field_to_u64<F: FieldExt>(f: &F) -> u64 {
+    let bytes = f.get_lower_128().to_le_bytes();
+    u64::from_le_bytes(bytes[0..8].try_into().unwrap())
+}
+
+/// the value of f if its canonical representative is below 2^64
+pub fn field_to_u64_checked<F: FieldExt>(f: &F) -> Option<u64> {
+    let repr = f.to_repr();
+    let bytes = repr.as_ref();
+    if bytes[8..].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Some(u64::from_le_bytes(bytes[0..8].try_into().unwrap()))
+}
+
+/// split v into N little endian limbs of the given bit width (1 ..= 32),
+/// the limbs must cover v: the bits of v above N * bits are not allowed
+pub fn to_limbs<F: FieldExt, const N: usize>(v: u32, bits: u32) -> [F; N] {
+    assert!(bits > 0 && bits <= 32, "limbs of {} bits", bits);
+    debug_assert!((N as u32) * bits >= 32 || (v as u64) >> ((N as u32) * bits) == 0,
+        "{} does not fit in {} limbs of {} bits", v, N, bits);
+    let mask = (1u64 << bits) - 1;
+    let mut rem = v as u64;
+    [0; N].map(|_| {
+        let limb = F::from(rem & mask);
+        rem >>= bits;
+        limb
+    })
+}
+
+/// recompose the little endian limbs of the given bit width into a u32
+pub fn limbs_to_value<F: FieldExt>(limbs: &[F], bits: u32) -> u32 {
+    assert!(bits > 0 && bits <= 32, "limbs of {} bits", bits);
+    limbs.iter().rev().fold(0u64, |acc, limb| {
+        debug_assert!(field_to_u64(limb) >> bits == 0, "limb wider than {} bits", bits);
+        (acc << bits) + field_to_u64(limb)
+    }) as u32
+}
+
+pub fn u32_to_limbs<F: FieldExt>(v: u32) -> [F; 4] {
+    to_limbs(v, 8)
+}
+
+pub fn u64_to_limbs<F: FieldExt>(v: u64) -> [F; 8] {
+    let mut rem = v;
+    let mut r = vec![];
+    for _ in 0..8 {
+        r.append(&mut vec![F::from(rem % 256)]);
+        rem = rem/256;
+    }
+    r.try_into().unwrap()
+}
+
+/* FIXME should not get value based on cell in new halo2 */
+pub fn cell_to_value<F: FieldExt>(cell: &AssignedCell<F, F>) -> F {
+    //cell.value().map_or(0, |x| field_to_u32(x))
+    let mut r = F::zero();
+    cell.value().map(|x| { r = *x });
+    r
+}
+
+
+
+/* FIXME should not get value based on cell in new halo2 */
+pub fn cell_to_u32<F: FieldExt>(cell: &AssignedCell<F, F>) -> u32 {
+    //cell.value().map_or(0, |x| field_to_u32(x))
+    let mut r = 0;
+    cell.value().map(|x| { r = field_to_u32(x) });
+    r
+}
+
+pub fn cell_to_limbs<F: FieldExt>(cell: &AssignedCell<F, F>) -> [F; 4] {
+    let a = cell_to_u32(cell);
+    u32_to_limbs(a)
+}
+
+/// the little endian bytes of a u32 cell, the raw counterpart of cell_to_limbs
+pub fn cell_to_bytes<F: FieldExt>(cell: &AssignedCell<F, F>) -> [u8; 4] {
+    cell_to_u32(cell).to_le_bytes()
+}
+
+/// the value of a cell holding up to 64 bits such as a message length,
+/// None if it does not fit
+pub fn cell_to_u64<F: FieldExt>(cell: &AssignedCell<F, F>) -> Option<u64> {
+    field_to_u64_checked(&cell_to_value(cell))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::arithmetic::Field;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{field_to_u64, field_to_u64_checked, limbs_to_value, to_limbs, u32_to_limbs};
+
+    #[test]
+    fn test_limbs_round_trip() {
+        for v in [0, 1, 0x12345678, 0x8000_0001, 0xffff_ffff] {
+            assert_eq!(to_limbs::<Fr, 4>(v, 8), u32_to_limbs::<Fr>(v));
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 4>(v, 8), 8), v);
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 8>(v, 4), 4), v);
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 2>(v, 16), 16), v);
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 1>(v, 32), 32), v);
+            // the c rotation splits at 22 bits, 10 bit limbs need 4 limbs
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 2>(v, 22), 22), v);
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 4>(v, 10), 10), v);
+            assert_eq!(limbs_to_value(&to_limbs::<Fr, 3>(v, 11), 11), v);
+        }
+        let limbs = to_limbs::<Fr, 2>(0xffff_ffff, 22);
+        assert_eq!(limbs, [Fr::from(0x3f_ffff), Fr::from(0x3ff)]);
+        assert_eq!(to_limbs::<Fr, 3>(5, 11), [Fr::from(5), Fr::zero(), Fr::zero()]);
+    }
+
+    #[test]
+    fn test_field_to_u64_checked() {
+        let max = Fr::from(u64::MAX);
+        assert_eq!(field_to_u64_checked(&max), Some(u64::MAX));
+        assert_eq!(field_to_u64_checked(&Fr::from(1u64 << 33)), Some(1 << 33));
+        assert_eq!(field_to_u64_checked(&Fr::zero()), Some(0));
+
+        // just above u64::MAX the unchecked conversion wraps around
+        for delta in [1u64, 2, 0xffff] {
+            let above = max + Fr::from(delta);
+            assert_eq!(field_to_u64_checked(&above), None);
+            assert_eq!(field_to_u64(&above), delta - 1);
+        }
+        assert_eq!(field_to_u64_checked(&-Fr::one()), None);
+    }
+}
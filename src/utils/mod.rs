@@ -1,58 +1,54 @@
-use halo2_proofs::arithmetic::FieldExt;
-use halo2_proofs::circuit::AssignedCell;
-use num_bigint::BigUint;
-
-pub fn field_to_bn<F: FieldExt>(f: &F) -> BigUint {
-    let bytes = f.to_repr();
-    BigUint::from_bytes_le(bytes.as_ref())
-}
-
-pub fn bn_to_field<F: FieldExt>(bn: &BigUint) -> F {
-    let mut bytes = bn.to_bytes_le();
-    bytes.resize(64, 0);
-    F::from_bytes_wide(&bytes.try_into().unwrap())
+// Field and cell helpers need halo2 and come with the std feature, the
+// digest helpers below are kept to core and alloc.
+
+use alloc::format;
+use alloc::string::String;
+
+#[cfg(feature = "std")]
+mod field;
+#[cfg(feature = "std")]
+pub use field::*;
+
+/// the canonical RIPEMD-160 digest of the chaining state, every word is
+/// serialized in little endian
+pub fn words_to_digest_bytes(words: [u32; 5]) -> [u8; 20] {
+    let mut bytes = [0; 20];
+    for (chunk, w) in bytes.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    bytes
 }
 
-pub fn field_to_u32<F: FieldExt>(f: &F) -> u32 {
-    f.get_lower_32()
+/// the five little endian words of a digest, inverse of words_to_digest_bytes
+pub fn digest_bytes_to_words(digest: [u8; 20]) -> [u32; 5] {
+    core::array::from_fn(|i| u32::from_le_bytes(digest[i * 4..i * 4 + 4].try_into().unwrap()))
 }
 
-pub fn field_to_u64<F: FieldExt>(f: &F) -> u64 {
-    let bytes = f.get_lower_128().to_le_bytes();
-    u64::from_le_bytes(bytes[0..8].try_into().unwrap())
+/// lower case hex of a digest, as printed by the reference implementations
+pub fn digest_to_hex(digest: [u8; 20]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
-pub fn u32_to_limbs<F: FieldExt>(v: u32) -> [F; 4] {
-    let mut rem = v;
-    let mut r = vec![];
-    for _ in 0..4 {
-        r.append(&mut vec![F::from((rem % 256) as u64)]);
-        rem = rem/256;
+/// parse 40 hex digits (either case) into a digest, None on any other input
+pub fn digest_from_hex(s: &str) -> Option<[u8; 20]> {
+    if s.len() != 40 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
     }
-    r.try_into().unwrap()
-}
-
-/* FIXME should not get value based on cell in new halo2 */
-pub fn cell_to_value<F: FieldExt>(cell: &AssignedCell<F, F>) -> F {
-    //cell.value().map_or(0, |x| field_to_u32(x))
-    let mut r = F::zero();
-    cell.value().map(|x| { r = *x });
-    r
-}
-
-
-
-/* FIXME should not get value based on cell in new halo2 */
-pub fn cell_to_u32<F: FieldExt>(cell: &AssignedCell<F, F>) -> u32 {
-    //cell.value().map_or(0, |x| field_to_u32(x))
-    let mut r = 0;
-    cell.value().map(|x| { r = field_to_u32(x) });
-    r
+    let mut digest = [0; 20];
+    for (i, b) in digest.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(digest)
 }
 
-pub fn cell_to_limbs<F: FieldExt>(cell: &AssignedCell<F, F>) -> [F; 4] {
-    let a = cell_to_u32(cell);
-    u32_to_limbs(a)
+/// trace through the log crate with the debug-trace feature,
+/// the call and its arguments are compiled out otherwise
+#[macro_export]
+macro_rules! debug_trace {
+    ($($arg: tt)*) => {{
+        #[cfg(feature = "debug-trace")]
+        { log::trace!($($arg)*); }
+    }};
 }
 
 #[macro_export]
@@ -140,3 +136,25 @@ macro_rules! value_for_assign {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::{digest_bytes_to_words, digest_from_hex, digest_to_hex, words_to_digest_bytes};
+    use crate::host::rmd160::{rmd160_block_words, message_blocks, rmd160_digest, H0};
+
+    #[test]
+    fn test_digest_hex() {
+        let empty = "9c1185a5c5e9fc54612808977ee8f548b2258d31";
+        assert_eq!(digest_to_hex(rmd160_digest(b"")), empty);
+        assert_eq!(digest_from_hex(empty), Some(rmd160_digest(b"")));
+        assert_eq!(digest_from_hex(&empty.to_uppercase()), Some(rmd160_digest(b"")));
+
+        // the state words after the single padding block of the empty message
+        let words = rmd160_block_words(H0, message_blocks(b"")[0]);
+        assert_eq!(digest_to_hex(words_to_digest_bytes(words)), empty);
+        assert_eq!(digest_bytes_to_words(words_to_digest_bytes(words)), words);
+        assert_eq!(words_to_digest_bytes([0x01020304, 0, 0, 0, 0])[..4], [4, 3, 2, 1]);
+
+        assert_eq!(digest_from_hex(&empty[1..]), None);
+        assert_eq!(digest_from_hex(&empty.replace('9', "g")), None);
+    }
+}
@@ -0,0 +1,31 @@
+// Compile check: the host reference and the digest helpers build
+// without std, only with alloc.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use mylib::host::rmd160::{hash_prepadded, hmac_rmd160, message_blocks, rmd160_digest, RoundSchedule};
+use mylib::utils::{digest_from_hex, digest_to_hex, words_to_digest_bytes};
+
+pub fn hex_digest(data: &[u8]) -> String {
+    digest_to_hex(rmd160_digest(data))
+}
+
+pub fn check_digest(data: &[u8], expected_hex: &str) -> bool {
+    digest_from_hex(expected_hex) == Some(hash_prepadded(&message_blocks(data)))
+}
+
+pub fn hmac(key: &[u8], message: &[u8]) -> [u8; 20] {
+    hmac_rmd160(key, message)
+}
+
+pub fn schedule_is_valid() -> bool {
+    RoundSchedule::rmd160().validate().is_ok()
+}
+
+pub fn state_bytes(words: [u32; 5]) -> [u8; 20] {
+    words_to_digest_bytes(words)
+}